@@ -0,0 +1,28 @@
+//! Calls whose argument terms differ in size only by a branch that never
+//! runs. Arguments are evaluated where they are rather than copied into the
+//! frame, so both should take about as long.
+
+mod common;
+
+/// A loop passing `dead` in a branch of its argument that's never taken.
+fn countdown(dead: &str) -> String {
+    format!("
+        let countdown = fn (n) => {{ if (n == 0) {{ 0 }} else {{ countdown(if (n < 0) {{ {dead} }} else {{ n - 1 }}) }} }};
+        countdown(100000)
+    ")
+}
+
+/// A term of `size` nested tuples.
+fn nested(size: usize) -> String {
+    (0..size).fold("0".to_string(), |x, i| format!("({i}, {x})"))
+}
+
+fn main() {
+    let small = common::program("small", &countdown("0"));
+    let large = common::program("large", &countdown(&nested(200)));
+    let with_small = common::time("countdown(100000), small argument", 10, || common::run(&small, &[]));
+    let with_large = common::time("countdown(100000), large argument", 10, || common::run(&large, &[]));
+    println!("the large argument costs {:.1}x the small one", with_large.as_secs_f64() / with_small.as_secs_f64());
+    std::fs::remove_file(small).unwrap();
+    std::fs::remove_file(large).unwrap();
+}
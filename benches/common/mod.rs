@@ -0,0 +1,46 @@
+//! Helpers shared by the benchmarks, which run with `cargo bench` and print
+//! how long each case took, the fastest of a few runs.
+
+#![allow(dead_code)]
+
+use std::{
+    path::PathBuf,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+/// Times `case` over `runs` runs after a warm-up one, printing and
+/// returning the fastest.
+pub fn time(name: &str, runs: u32, mut case: impl FnMut()) -> Duration {
+    case();
+    let fastest = (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            case();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default();
+    println!("{name:<40} {fastest:>12.3?}");
+    fastest
+}
+
+/// A source file under the system's temporary directory holding `source`,
+/// named so benchmarks running at the same time don't share it.
+pub fn program(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("caramuru-bench-{}-{name}.rinha", std::process::id()));
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+/// Runs the binary on the program at `path`, with `args` before it,
+/// throwing its output away.
+pub fn run(path: &PathBuf, args: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_caramuru"))
+        .args(args)
+        .arg(path)
+        .stdout(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success(), "the benchmark should run");
+}
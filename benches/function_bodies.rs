@@ -0,0 +1,30 @@
+//! Calls to recursive functions whose bodies differ in size only by a
+//! branch that never runs. Calls share the body of the function rather
+//! than copying it, so both should take about as long.
+
+mod common;
+
+/// `fib`, with `dead` in a branch that's never taken.
+fn fib(dead: &str) -> String {
+    format!("
+        let fib = fn (n) => {{
+            if (n < 0) {{ {dead} }} else {{ if (n < 2) {{ n }} else {{ fib(n - 1) + fib(n - 2) }} }}
+        }};
+        fib(20)
+    ")
+}
+
+/// A term of `size` nested tuples.
+fn nested(size: usize) -> String {
+    (0..size).fold("0".to_string(), |x, i| format!("({i}, {x})"))
+}
+
+fn main() {
+    let small = common::program("small", &fib("0"));
+    let large = common::program("large", &fib(&nested(200)));
+    let with_small = common::time("fib(20), small body", 10, || common::run(&small, &[]));
+    let with_large = common::time("fib(20), large body", 10, || common::run(&large, &[]));
+    println!("the large body costs {:.1}x the small one", with_large.as_secs_f64() / with_small.as_secs_f64());
+    std::fs::remove_file(small).unwrap();
+    std::fs::remove_file(large).unwrap();
+}
@@ -0,0 +1,20 @@
+//! A loop reading many globals, which are looked up by their interned
+//! symbols rather than by name, so reading one costs about the same however
+//! many there are.
+
+mod common;
+
+/// `count` globals, and a loop adding all of them up `times` times.
+fn program(count: usize, times: usize) -> String {
+    let bindings: String = (0..count).map(|i| format!("let variable_number_{i} = {i};\n")).collect();
+    let sum = (0..count).map(|i| format!("variable_number_{i}")).collect::<Vec<_>>().join(" + ");
+    format!("{bindings}let loop = fn (n, acc) => {{ if (n == 0) {{ acc }} else {{ loop(n - 1, acc + ({sum})) }} }};\nloop({times}, 0)")
+}
+
+fn main() {
+    for count in [1, 10, 100] {
+        let path = common::program(&format!("globals-{count}"), &program(count, 1000000 / count));
+        common::time(&format!("1000000 reads, {count} distinct globals"), 5, || common::run(&path, &[]));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,25 @@
+//! Throughput of a program that prints in a loop, against the same loop
+//! without the prints. Output goes to the null device, so what's left is
+//! the cost of getting each value there.
+
+mod common;
+
+const PRINTING: &str = "
+    let loop = fn (n) => { if (n == 0) { 0 } else { let _ = print(n); loop(n - 1) } };
+    loop(100000)
+";
+
+const SILENT: &str = "
+    let loop = fn (n) => { if (n == 0) { 0 } else { let _ = n; loop(n - 1) } };
+    loop(100000)
+";
+
+fn main() {
+    let printing = common::program("printing", PRINTING);
+    let silent = common::program("silent", SILENT);
+    let with = common::time("loop 100000 times, printing", 5, || common::run(&printing, &[]));
+    let without = common::time("loop 100000 times, silent", 5, || common::run(&silent, &[]));
+    println!("printing costs {:.1}x the silent loop", with.as_secs_f64() / without.as_secs_f64());
+    std::fs::remove_file(printing).unwrap();
+    std::fs::remove_file(silent).unwrap();
+}
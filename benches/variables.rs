@@ -0,0 +1,37 @@
+//! Reading variables from the slots the resolver gives them: `fib(30)`,
+//! then a loop reading the same value as a local, a captured variable and
+//! a global.
+
+mod common;
+
+const FIB: &str = "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; print(fib(30))";
+
+const LOCAL: &str = "
+    let loop = fn (n, x) => { if (n == 0) { x } else { loop(n - 1, (x + x) - (x + x) + x) } };
+    loop(1000000, 1)
+";
+
+const CAPTURED: &str = "
+    let outer = fn (x) => {
+        let loop = fn (n) => { if (n == 0) { x } else { let _ = (x + x) - (x + x) + x; loop(n - 1) } };
+        loop(1000000)
+    };
+    outer(1)
+";
+
+const GLOBAL: &str = "
+    let x = 1;
+    let loop = fn (n) => { if (n == 0) { x } else { let _ = (x + x) - (x + x) + x; loop(n - 1) } };
+    loop(1000000)
+";
+
+fn main() {
+    let fib = common::program("fib", FIB);
+    common::time("fib(30)", 3, || common::run(&fib, &[]));
+    std::fs::remove_file(fib).unwrap();
+    for (name, source) in [("local", LOCAL), ("captured", CAPTURED), ("global", GLOBAL)] {
+        let path = common::program(name, source);
+        common::time(&format!("5000000 reads of a {name} variable"), 5, || common::run(&path, &[]));
+        std::fs::remove_file(path).unwrap();
+    }
+}
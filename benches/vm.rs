@@ -0,0 +1,13 @@
+//! `fib(30)` on the tree-walking interpreter and on the bytecode machine.
+
+mod common;
+
+const FIB: &str = "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(30)";
+
+fn main() {
+    let fib = common::program("fib", FIB);
+    let interpreted = common::time("fib(30), interpreted", 3, || common::run(&fib, &[]));
+    let compiled = common::time("fib(30), on the vm", 3, || common::run(&fib, &["--vm"]));
+    println!("the vm is {:.1}x as fast", interpreted.as_secs_f64() / compiled.as_secs_f64());
+    std::fs::remove_file(fib).unwrap();
+}
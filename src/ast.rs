@@ -1,8 +1,25 @@
-use std::{fmt::Debug, rc::Rc};
+use std::{cell::{Cell, RefCell}, collections::HashMap, fmt::Debug, rc::Rc};
 
 /// File definition, it contains all the statements,
 /// the module name, and a base location for it as anchor
 /// for the statements.
+///
+/// Files serialize to the JSON AST that `--emit-json` prints, and read back
+/// from it unchanged:
+///
+/// ```
+/// use caramuru::{ast::File, parser};
+///
+/// let source = r#"
+///     let f = fn (a, b) => { if (a < b) { print("lt") } else { (a, b, true) } };
+///     let x = first((1, 2)) + second((3, 4)) * 1.5;
+///     f(x, -1)
+/// "#;
+/// let file = parser::parse_or_report("all.rinha", source).unwrap();
+/// let json = serde_json::to_string(&file).unwrap();
+/// let read: File = serde_json::from_str(&json).unwrap();
+/// assert_eq!(serde_json::to_string(&read).unwrap(), json);
+/// ```
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct File {
     pub name: String,
@@ -22,6 +39,12 @@ impl<T: Element> Element for Box<T> {
     }
 }
 
+thread_local! {
+    /// The text of the files parsed on this thread, by name, so that
+    /// locations can be shown as lines and columns.
+    static SOURCES: RefCell<HashMap<String, Rc<str>>> = RefCell::default();
+}
+
 #[derive(Default, Hash, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Location {
     pub start: usize,
@@ -38,6 +61,41 @@ impl Location {
             filename: filename.into(),
         }
     }
+
+    /// Computes the 1-based line and column of the start of this location
+    /// in `source`, the text it was parsed from. Columns count characters.
+    pub fn line_column(&self, source: &str) -> (usize, usize) {
+        let before = &source.as_bytes()[..self.start.min(source.len())];
+        let line = before.iter().filter(|x| **x == b'\n').count() + 1;
+        let line_start = before.iter().rposition(|x| *x == b'\n').map_or(0, |x| x + 1);
+        let column = String::from_utf8_lossy(&before[line_start..]).chars().count() + 1;
+        (line, column)
+    }
+
+    /// Remembers `source` as the text of `filename`, for locations in that
+    /// file to show their line and column. The parser calls this.
+    pub fn register_source(filename: &str, source: &str) {
+        SOURCES.with(|sources| sources.borrow_mut().insert(filename.to_string(), source.into()));
+    }
+
+    /// The text of the file this location is in, when it was parsed on this
+    /// thread rather than read as a JSON AST.
+    pub fn source(&self) -> Option<Rc<str>> {
+        SOURCES.with(|sources| sources.borrow().get(&self.filename).cloned())
+    }
+
+    /// Quotes the line of `source` this location starts on, underlining the
+    /// part of it the location covers with carets, at least one.
+    pub fn snippet(&self, source: &str) -> String {
+        let start = self.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |x| x + 1);
+        let line_end = source[start..].find('\n').map_or(source.len(), |x| start + x);
+        let line = &source[line_start..line_end];
+        // Tabs are kept so the carets line up however wide they're shown.
+        let before: String = source[line_start..start].chars().map(|x| if x == '\t' { '\t' } else { ' ' }).collect();
+        let width = source[start..self.end.clamp(start, line_end)].chars().count().max(1);
+        format!("{line}\n{before}{}", "^".repeat(width))
+    }
 }
 
 impl Debug for Location {
@@ -46,6 +104,29 @@ impl Debug for Location {
     }
 }
 
+/// Shows `file:line:col` when the source of the file is known, and the byte
+/// offset otherwise, like with a JSON AST:
+///
+/// ```
+/// use caramuru::ast::Location;
+///
+/// assert_eq!(Location::new(28, 30, "file.json").to_string(), "file.json, byte 28");
+///
+/// Location::register_source("file.rinha", "let x = 1;\nlet y = z;");
+/// assert_eq!(Location::new(19, 20, "file.rinha").to_string(), "file.rinha:2:9");
+/// ```
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.source() {
+            Some(source) => {
+                let (line, column) = self.line_column(&source);
+                write!(f, "{}:{line}:{column}", self.filename)
+            },
+            None => write!(f, "{}, byte {}", self.filename, self.start),
+        }
+    }
+}
+
 impl From<Location> for miette::SourceSpan {
     fn from(value: Location) -> Self {
         Self::from(value.start..value.end)
@@ -80,10 +161,25 @@ impl Element for Error {
 pub struct If {
     pub condition: Box<Term>,
     pub then: Box<Term>,
-    pub otherwise: Box<Term>,
+
+    /// Generated ASTs may leave the else branch out, in which case a false
+    /// condition evaluates to void. The parser always sets it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otherwise: Option<Box<Term>>,
     pub location: Location,
 }
 
+/// A binding, and the term it's visible in.
+///
+/// Blocks with several statements, like `{ print(1); 2 }`, are read as lets
+/// of `_`, so they need nothing of their own to run:
+///
+/// ```
+/// use caramuru::RuntimeValue;
+///
+/// let source = "let f = fn (n) => { let m = n * 2; print(m); m + 1 }; f(3)";
+/// assert_eq!(caramuru::eval_str(source).unwrap(), RuntimeValue::Int(7));
+/// ```
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Let {
     pub name: crate::parser::Var,
@@ -135,13 +231,29 @@ impl Element for Int {
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// Float is a floating-point value like `0.5`, `3.14`, etc.
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Float {
+    /// The value of the float.
+    pub value: f64,
+
+    /// The location of the float in the source code.
+    pub location: Location,
+}
+
+impl Element for Float {
+    fn location(&self) -> &Location {
+        &self.location
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub enum BinaryOp {
     Add, // Add
     Sub, // Subtract
     Mul, // Multiply
     Div, // Divide
-    Rem, // Rem
+    Rem, // Remainder of truncated division, with the sign of the dividend
     Eq,  // Equal
     Neq, // Not equal
     Lt,  // Less than
@@ -150,6 +262,37 @@ pub enum BinaryOp {
     Gte, // Greater than or equal to
     And, // And
     Or,  // Or
+    BitAnd, // Bitwise and
+    BitOr,  // Bitwise or
+    BitXor, // Bitwise exclusive or
+    Shl,    // Shift left
+    Shr,    // Shift right, keeping the sign
+}
+
+impl std::fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Eq => "==",
+            BinaryOp::Neq => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Gt => ">",
+            BinaryOp::Lte => "<=",
+            BinaryOp::Gte => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
+        };
+        write!(f, "{symbol}")
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -182,8 +325,14 @@ impl Element for Call {
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Function {
     pub parameters: Vec<crate::parser::Var>,
-    pub value: Box<Term>,
+    /// The body, shared between the closures created from this function.
+    pub value: Rc<Term>,
     pub location: Location,
+
+    /// How many slots a call needs for its parameters and `let` bindings,
+    /// set by [`crate::resolver`].
+    #[serde(skip)]
+    pub frame_size: Cell<usize>,
 }
 
 impl Element for Function {
@@ -232,9 +381,22 @@ impl Element for Second {
 pub struct Tuple {
     pub first: Box<Term>,
     pub second: Box<Term>,
+
+    /// Elements after the second one. Left out of the JSON of pairs, so
+    /// those stay as the spec describes them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rest: Vec<Term>,
+
     pub location: Location,
 }
 
+impl Tuple {
+    /// All the elements, in order.
+    pub fn elements(&self) -> impl Iterator<Item = &Term> {
+        [&*self.first, &*self.second].into_iter().chain(&self.rest)
+    }
+}
+
 impl Element for Tuple {
     fn location(&self) -> &Location {
         &self.location
@@ -246,6 +408,7 @@ impl Element for Tuple {
 pub enum Term {
     Error(Error),
     Int(Int),
+    Float(Float),
     Str(Str),
     Call(Call),
     Binary(Binary),
@@ -265,6 +428,7 @@ impl Element for Term {
         match self {
             Term::Error(arg0) => &arg0.location,
             Term::Int(arg0) => &arg0.location,
+            Term::Float(arg0) => &arg0.location,
             Term::Str(arg0) => &arg0.location,
             Term::Function(arg0) => &arg0.location,
             Term::Call(arg0) => arg0.location(),
@@ -0,0 +1,118 @@
+//! Decoding of gzip files (RFC 1952), so that large ASTs can be read
+//! compressed.
+
+use miniz_oxide::{inflate::stream::{inflate, InflateState}, DataFormat, MZError, MZFlush, MZStatus};
+
+/// Why a gzip file could not be decoded.
+#[derive(thiserror::Error, Debug)]
+pub enum GzipError {
+    #[error("not a gzip file")]
+    BadHeader,
+
+    #[error("truncated gzip file")]
+    Truncated,
+
+    #[error("corrupt gzip data: {0:?}")]
+    Corrupt(MZError),
+
+    #[error("gzip checksum mismatch: expected {expected:08x}, got {actual:08x}")]
+    ChecksumMismatch { expected: u32, actual: u32 },
+
+    #[error("gzip size mismatch: expected {expected} bytes, got {actual}")]
+    SizeMismatch { expected: u32, actual: usize },
+}
+
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+/// Decompresses a gzip file, concatenating the data of all its members.
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, GzipError> {
+    let mut data = Vec::new();
+    let mut rest = bytes;
+    loop {
+        rest = member(rest, &mut data)?;
+        if rest.is_empty() {
+            return Ok(data)
+        }
+    }
+}
+
+/// Decompresses the member at the start of `bytes` onto `data`, returning
+/// the bytes that follow it.
+fn member<'a>(bytes: &'a [u8], data: &mut Vec<u8>) -> Result<&'a [u8], GzipError> {
+    let header = bytes.get(..10).ok_or(GzipError::Truncated)?;
+    // Magic number and the deflate compression method.
+    if header[..3] != [0x1f, 0x8b, 8] {
+        return Err(GzipError::BadHeader)
+    }
+    let flags = header[3];
+    let mut rest = &bytes[10..];
+    if flags & FEXTRA != 0 {
+        let len = match rest {
+            [lo, hi, ..] => u16::from_le_bytes([*lo, *hi]) as usize,
+            _ => return Err(GzipError::Truncated),
+        };
+        rest = rest.get(2 + len..).ok_or(GzipError::Truncated)?;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            // Zero terminated strings.
+            let end = rest.iter().position(|x| *x == 0).ok_or(GzipError::Truncated)?;
+            rest = &rest[end + 1..];
+        }
+    }
+    if flags & FHCRC != 0 {
+        rest = rest.get(2..).ok_or(GzipError::Truncated)?;
+    }
+    let start = data.len();
+    let consumed = inflate_raw(rest, data)?;
+    let member = &data[start..];
+    // The trailer follows the deflate stream and holds the CRC-32 and the
+    // size modulo 2^32 of the data.
+    let (expected_crc, expected_size) = match rest.get(consumed..consumed + 8) {
+        Some([a, b, c, d, e, f, g, h]) => (u32::from_le_bytes([*a, *b, *c, *d]), u32::from_le_bytes([*e, *f, *g, *h])),
+        _ => return Err(GzipError::Truncated),
+    };
+    let actual_crc = crc32(member);
+    if expected_crc != actual_crc {
+        return Err(GzipError::ChecksumMismatch { expected: expected_crc, actual: actual_crc });
+    }
+    if expected_size != member.len() as u32 {
+        return Err(GzipError::SizeMismatch { expected: expected_size, actual: member.len() });
+    }
+    Ok(&rest[consumed + 8..])
+}
+
+/// Inflates the raw deflate stream at the start of `input` onto `data`,
+/// returning how many bytes of `input` it took up.
+fn inflate_raw(input: &[u8], data: &mut Vec<u8>) -> Result<usize, GzipError> {
+    let mut state = InflateState::new_boxed(DataFormat::Raw);
+    let mut chunk = vec![0; 1 << 15];
+    let mut consumed = 0;
+    loop {
+        let result = inflate(&mut state, &input[consumed..], &mut chunk, MZFlush::None);
+        consumed += result.bytes_consumed;
+        data.extend_from_slice(&chunk[..result.bytes_written]);
+        match result.status {
+            Ok(MZStatus::StreamEnd) => return Ok(consumed),
+            Ok(_) => {}
+            // No progress can be made: the input ended before the stream did.
+            Err(MZError::Buf) => return Err(GzipError::Truncated),
+            Err(e) => return Err(GzipError::Corrupt(e)),
+        }
+    }
+}
+
+/// The CRC-32 (ISO 3309) of `data`, as stored in gzip trailers.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
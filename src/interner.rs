@@ -0,0 +1,48 @@
+//! Gives every distinct name a small number, so that top-level bindings
+//! can be looked up without hashing and comparing strings.
+
+use std::collections::HashMap;
+
+/// A name, as numbered by an [`Interner`].
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+/// Numbers names in the order they're first seen. The same name always
+/// gets the same symbol from the same interner.
+#[derive(Default)]
+pub struct Interner {
+    symbols: HashMap<String, Symbol>,
+    /// Names by symbol, to tell which name a symbol stands for.
+    names: Vec<String>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(name) {
+            return *symbol
+        }
+        let symbol = Symbol(self.names.len() as u32);
+        self.symbols.insert(name.to_string(), symbol);
+        self.names.push(name.to_string());
+        symbol
+    }
+
+    /// A new symbol for `name` that [`Interner::intern`] never hands out,
+    /// for a binding that hides a global of the same name without
+    /// replacing it.
+    pub fn fresh(&mut self, name: &str) -> Symbol {
+        let symbol = Symbol(self.names.len() as u32);
+        self.names.push(name.to_string());
+        symbol
+    }
+
+    /// Whether a symbol is the one `name` is interned as, rather than one
+    /// from [`Interner::fresh`].
+    pub fn is_interned(&self, symbol: Symbol) -> bool {
+        self.symbols.get(self.name(symbol)) == Some(&symbol)
+    }
+
+    pub fn name(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.0 as usize]
+    }
+}
@@ -0,0 +1,278 @@
+//! Functions built into the interpreter. They are called like any other
+//! function, but only when no binding with the same name is in scope, so
+//! programs can still define their own.
+
+use std::{cell::RefCell, io::BufRead, rc::Rc};
+
+use crate::{ast::Location, Config, EvalResult, Interpreter, InterpreterError, RuntimeValue};
+
+/// A built-in function.
+pub struct Intrinsic {
+    pub name: &'static str,
+    /// The number of arguments, or `None` for any number.
+    pub arity: Option<usize>,
+    /// Takes the arguments, where the call is and the interpreter running
+    /// it.
+    pub call: fn(Vec<RuntimeValue>, &Location, &mut Interpreter) -> EvalResult,
+    /// Whether it reads the environment of the process, which
+    /// [`Config::env`] can deny.
+    pub env: bool,
+    /// Whether it changes a value that other code can see, like `push`.
+    pub mutates: bool,
+    /// Whether it reads from [`Interpreter::input`], which needs
+    /// [`Config::io`].
+    pub io: bool,
+}
+
+/// Every built-in function. Failures are errors like any other:
+///
+/// ```
+/// use caramuru::{eval_str, RuntimeValue};
+///
+/// assert_eq!(eval_str("pow(2, 10)").unwrap(), RuntimeValue::Int(1024));
+/// assert_eq!(eval_str("pow(2, 63)").unwrap_err().to_string(), "invalid argument passed to pow: 2 to the power of 63 overflows");
+/// assert!(eval_str("pow(2, -1)").unwrap_err().to_string().contains("negative exponent"));
+/// ```
+pub const INTRINSICS: &[Intrinsic] = &[
+    Intrinsic { name: "to_int", arity: Some(1), call: to_int, env: false, mutates: false, io: false },
+    Intrinsic { name: "to_str", arity: Some(1), call: to_str, env: false, mutates: false, io: false },
+    Intrinsic { name: "get", arity: Some(2), call: get, env: false, mutates: false, io: false },
+    Intrinsic { name: "len", arity: Some(1), call: len, env: false, mutates: false, io: false },
+    Intrinsic { name: "mod", arity: Some(2), call: modulo, env: false, mutates: false, io: false },
+    Intrinsic { name: "get_env", arity: Some(1), call: get_env, env: true, mutates: false, io: false },
+    Intrinsic { name: "array", arity: Some(0), call: array, env: false, mutates: false, io: false },
+    Intrinsic { name: "push", arity: Some(2), call: push, env: false, mutates: true, io: false },
+    Intrinsic { name: "str_len", arity: Some(1), call: str_len, env: false, mutates: false, io: false },
+    Intrinsic { name: "substr", arity: Some(3), call: substr, env: false, mutates: false, io: false },
+    Intrinsic { name: "concat", arity: None, call: concat, env: false, mutates: false, io: false },
+    Intrinsic { name: "assert", arity: None, call: assert, env: false, mutates: false, io: false },
+    Intrinsic { name: "read_line", arity: Some(0), call: read_line, env: false, mutates: false, io: true },
+    Intrinsic { name: "pow", arity: Some(2), call: pow, env: false, mutates: false, io: false },
+];
+
+/// Finds the intrinsic called `name`, if `config` allows it.
+pub fn lookup(name: &str, config: &Config) -> Option<&'static Intrinsic> {
+    INTRINSICS.iter().find(|x| x.name == name && (config.env || !x.env) && (config.io || !x.io))
+}
+
+/// Parses a decimal integer out of a string, like `to_int("-7")`.
+/// Surrounding whitespace is trimmed, so that lines read from text data
+/// parse as-is.
+fn to_int(arguments: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    match &arguments[..] {
+        [RuntimeValue::Str(x)] => x.trim().parse().map(RuntimeValue::Int).map_err(|_| {
+            InterpreterError::InvalidArgument { name: "to_int".to_string(), message: format!("\"{x}\" is not an integer") }
+        }),
+        _ => Err(InterpreterError::type_mismatch("to_int expects a string")),
+    }
+}
+
+/// Converts any value to the text `print` would write for it.
+fn to_str(arguments: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    Ok(RuntimeValue::Str(arguments[0].to_string()))
+}
+
+/// Reads the element of a tuple or an array at a zero-based index, like
+/// `get((1, 2, 3), 2)`. `first` and `second` only accept pairs.
+fn get(arguments: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    let element = |elements: &[RuntimeValue], i: i32, type_name: &str| {
+        usize::try_from(i).ok()
+            .and_then(|i| elements.get(i))
+            .cloned()
+            .ok_or_else(|| InterpreterError::InvalidArgument {
+                name: "get".to_string(),
+                message: format!("index {i} is out of bounds for {type_name} of {} elements", elements.len()),
+            })
+    };
+    match &arguments[..] {
+        [RuntimeValue::Tuple(x), RuntimeValue::Int(i)] => element(x, *i, "a tuple"),
+        [RuntimeValue::Array(x), RuntimeValue::Int(i)] => element(&x.borrow(), *i, "an array"),
+        _ => Err(InterpreterError::type_mismatch("get expects a tuple or an array and an integer")),
+    }
+}
+
+/// Counts the elements of a tuple or an array.
+fn len(arguments: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    let len = match &arguments[..] {
+        [RuntimeValue::Tuple(x)] => x.len(),
+        [RuntimeValue::Array(x)] => x.borrow().len(),
+        _ => return Err(InterpreterError::type_mismatch("len expects a tuple or an array")),
+    };
+    i32::try_from(len).map(RuntimeValue::Int).map_err(|_| {
+        InterpreterError::InvalidArgument { name: "len".to_string(), message: "too many elements".to_string() }
+    })
+}
+
+/// The Euclidean remainder, which is never negative whatever the signs of
+/// the operands: `mod(-7, 3)` is 2, while `-7 % 3` truncates to -1.
+fn modulo(arguments: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    match &arguments[..] {
+        [RuntimeValue::Int(_), RuntimeValue::Int(0)] => Err(InterpreterError::InvalidArgument {
+            name: "mod".to_string(),
+            message: "division by zero".to_string(),
+        }),
+        // Only `i32::MIN` by -1 wraps, and its remainder is 0 anyway.
+        [RuntimeValue::Int(l), RuntimeValue::Int(r)] => Ok(RuntimeValue::Int(l.wrapping_rem_euclid(*r))),
+        _ => Err(InterpreterError::type_mismatch("mod expects two integers")),
+    }
+}
+
+/// Reads an environment variable, like `get_env("HOME")`. Variables that
+/// aren't set read as an empty string, so that programs can test for them
+/// with `== ""`.
+fn get_env(arguments: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    match &arguments[..] {
+        [RuntimeValue::Str(x)] => {
+            let value = std::env::var_os(x).unwrap_or_default();
+            Ok(RuntimeValue::Str(value.to_string_lossy().into_owned()))
+        },
+        _ => Err(InterpreterError::type_mismatch("get_env expects a string")),
+    }
+}
+
+/// Makes a new, empty array.
+fn array(_: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    Ok(RuntimeValue::Array(Default::default()))
+}
+
+/// Appends a value to an array, like `push(xs, 1)`, and evaluates to the
+/// array. Every binding of the array sees the new element.
+fn push(mut arguments: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    let value = arguments.pop();
+    match (arguments.pop(), value) {
+        (Some(RuntimeValue::Array(x)), Some(value)) => {
+            // An array inside itself would print and compare forever.
+            if contains(&value, &x) {
+                return Err(InterpreterError::InvalidArgument {
+                    name: "push".to_string(),
+                    message: "an array can't be pushed into itself".to_string(),
+                })
+            }
+            x.borrow_mut().push(value);
+            Ok(RuntimeValue::Array(x))
+        },
+        _ => Err(InterpreterError::type_mismatch("push expects an array and a value")),
+    }
+}
+
+/// Tells whether `value` is `array` or holds it somewhere in its elements.
+fn contains(value: &RuntimeValue, array: &Rc<RefCell<Vec<RuntimeValue>>>) -> bool {
+    match value {
+        RuntimeValue::Array(x) => Rc::ptr_eq(x, array) || x.borrow().iter().any(|y| contains(y, array)),
+        RuntimeValue::Tuple(x) => x.iter().any(|y| contains(y, array)),
+        _ => false,
+    }
+}
+
+/// Counts the characters of a string, not its bytes: `str_len("é")` is 1.
+fn str_len(arguments: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    match &arguments[..] {
+        [RuntimeValue::Str(x)] => i32::try_from(x.chars().count()).map(RuntimeValue::Int).map_err(|_| {
+            InterpreterError::InvalidArgument { name: "str_len".to_string(), message: "string is too long".to_string() }
+        }),
+        _ => Err(InterpreterError::type_mismatch("str_len expects a string")),
+    }
+}
+
+/// Slices a string from the character at `start` up to, but not including,
+/// the one at `end`, like `substr("olá!", 1, 3)` for `"lá"`. Indices count
+/// characters, so a slice never splits one.
+fn substr(arguments: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    let (x, start, end) = match &arguments[..] {
+        [RuntimeValue::Str(x), RuntimeValue::Int(start), RuntimeValue::Int(end)] => (x, *start, *end),
+        _ => return Err(InterpreterError::type_mismatch("substr expects a string and two integers")),
+    };
+    let len = x.chars().count();
+    let range = usize::try_from(start).ok().zip(usize::try_from(end).ok()).filter(|(start, end)| start <= end && *end <= len);
+    match range {
+        Some((start, end)) => Ok(RuntimeValue::Str(x.chars().skip(start).take(end - start).collect())),
+        None => Err(InterpreterError::InvalidArgument {
+            name: "substr".to_string(),
+            message: format!("range {start}..{end} is out of bounds for a string of {len} characters"),
+        }),
+    }
+}
+
+/// Joins any number of strings, and numbers written as `print` would, like
+/// `concat("x = ", 1)`. The result is allocated once, unlike a chain of `+`.
+fn concat(arguments: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    let mut parts = Vec::with_capacity(arguments.len());
+    for argument in &arguments {
+        parts.push(match argument {
+            RuntimeValue::Str(x) => std::borrow::Cow::Borrowed(x.as_str()),
+            RuntimeValue::Int(_) | RuntimeValue::Float(_) => std::borrow::Cow::Owned(argument.to_string()),
+            _ => return Err(InterpreterError::type_mismatch("concat expects strings or numbers")),
+        });
+    }
+    Ok(RuntimeValue::Str(parts.concat()))
+}
+
+/// Fails unless its first argument is true or a nonzero integer, like
+/// `assert(x == 1, "x should be 1")`, and is void otherwise. The message
+/// is optional.
+fn assert(arguments: Vec<RuntimeValue>, location: &Location, _: &mut Interpreter) -> EvalResult {
+    let (condition, message) = match &arguments[..] {
+        [condition] => (condition, None),
+        [condition, RuntimeValue::Str(message)] => (condition, Some(message)),
+        _ => return Err(InterpreterError::type_mismatch("assert expects a condition and an optional string")),
+    };
+    let passed = match condition {
+        RuntimeValue::Bool(x) => *x,
+        RuntimeValue::Int(x) => *x != 0,
+        x => return Err(InterpreterError::type_mismatch(format!("assert expects a bool or an int, found {}", x.type_name()))),
+    };
+    match passed {
+        true => Ok(RuntimeValue::Void(())),
+        false => Err(InterpreterError::AssertionFailed {
+            message: message.cloned().unwrap_or_else(|| "assertion failed".to_string()),
+            location: location.clone(),
+        }),
+    }
+}
+
+/// Reads a line from the input, stdin by default, without the line break
+/// that ends it. At the end of the input it's void, which tells it apart
+/// from an empty line.
+fn read_line(_: Vec<RuntimeValue>, _: &Location, interpreter: &mut Interpreter) -> EvalResult {
+    let mut line = String::new();
+    let read = match &mut interpreter.input {
+        Some(input) => input.read_line(&mut line),
+        None => std::io::stdin().read_line(&mut line),
+    };
+    if read.map_err(InterpreterError::Input)? == 0 {
+        return Ok(RuntimeValue::Void(()))
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(RuntimeValue::Str(line))
+}
+
+/// Raises a number to a power, like `pow(2, 10)`. Integers stay integers,
+/// so their exponent can't be negative and the result has to fit in 32
+/// bits. With a float on either side, the result is a float.
+fn pow(arguments: Vec<RuntimeValue>, _: &Location, _: &mut Interpreter) -> EvalResult {
+    let invalid = |message: String| InterpreterError::InvalidArgument { name: "pow".to_string(), message };
+    match &arguments[..] {
+        [RuntimeValue::Int(base), RuntimeValue::Int(exponent)] => {
+            let Ok(power) = u32::try_from(*exponent) else {
+                return Err(invalid(format!("negative exponent {exponent} for the integer {base}")))
+            };
+            base.checked_pow(power)
+                .map(RuntimeValue::Int)
+                .ok_or_else(|| invalid(format!("{base} to the power of {exponent} overflows")))
+        },
+        [RuntimeValue::Int(_) | RuntimeValue::Float(_), RuntimeValue::Int(_) | RuntimeValue::Float(_)] => {
+            let float = |x: &RuntimeValue| match x {
+                RuntimeValue::Int(x) => *x as f64,
+                RuntimeValue::Float(x) => *x,
+                _ => unreachable!("matched as a number"),
+            };
+            Ok(RuntimeValue::Float(float(&arguments[0]).powf(float(&arguments[1]))))
+        },
+        _ => Err(InterpreterError::type_mismatch("pow expects two numbers")),
+    }
+}
@@ -0,0 +1,1510 @@
+//! A tree-walking interpreter for the rinha language.
+//!
+//! Programs are parsed from source with [`parser::parse_or_report`] or
+//! deserialized from a JSON AST into an [`ast::File`], then evaluated by an
+//! [`Interpreter`]:
+//!
+//! ```
+//! let file = caramuru::parser::parse_or_report("example.rinha", "let x = 40; x + 2").unwrap();
+//! let value = caramuru::eval(&file.expression).unwrap();
+//! assert_eq!(value.to_string(), "42");
+//! ```
+
+use std::{io::{BufRead, BufWriter, Write}, collections::{BTreeMap, HashMap}, rc::Rc};
+
+use lalrpop_util::lalrpop_mod;
+
+pub mod ast;
+pub mod gzip;
+pub mod interner;
+pub mod intrinsics;
+pub mod optimize;
+pub mod parser;
+pub mod printer;
+pub mod resolver;
+pub mod validate;
+pub mod vm;
+
+lalrpop_mod! {
+    #[allow(warnings)]
+    pub rinha
+}
+
+/// A frame of the call stack.
+pub struct Call {
+    /// Parameters and `let` bindings, indexed by [`resolver::Slot::Local`].
+    pub slots: Vec<RuntimeValue>,
+    /// Bindings captured by the closure running in this frame.
+    pub env: Option<Rc<Env>>,
+    /// The globals as the running closure saw them when it was defined.
+    pub globals: Option<Rc<Globals>>,
+}
+
+/// Top-level bindings, by the symbols their names were interned as.
+pub type Globals = HashMap<interner::Symbol, RuntimeValue>;
+
+/// A snapshot of the bindings visible where a closure was defined.
+pub struct Env {
+    /// The slots of the frame the closure was defined in.
+    pub vars: Vec<RuntimeValue>,
+    pub parent: Option<Rc<Env>>,
+}
+
+impl Env {
+    /// Reads a slot of the environment `depth` levels up, 1 being this one.
+    fn get(&self, depth: usize, index: usize) -> Option<&RuntimeValue> {
+        match depth {
+            1 => self.vars.get(index),
+            _ => self.parent.as_ref()?.get(depth - 1, index),
+        }
+    }
+}
+
+/// A function value together with the environment it was defined in.
+pub struct Closure {
+    pub function: ast::Function,
+    pub env: Option<Rc<Env>>,
+    pub globals: Rc<Globals>,
+    /// The name a `let` bound the closure to, so that its body can call
+    /// itself even though the snapshot in `env` predates the binding.
+    pub name: Option<String>,
+}
+
+impl Closure {
+    /// The argument values in the slots of a call to this closure.
+    fn arguments<'a>(&self, slots: &'a [RuntimeValue]) -> &'a [RuntimeValue] {
+        let offset = self.name.is_some() as usize;
+        &slots[offset..offset + self.function.parameters.len()]
+    }
+}
+
+pub type EvalResult = Result<RuntimeValue, InterpreterError>;
+
+/// Errors raised while evaluating a program. Messages leave out where the
+/// error happened, which [`InterpreterError::location`] tells.
+#[derive(thiserror::Error, Debug)]
+pub enum InterpreterError {
+    #[error("type mismatch: {message}")]
+    TypeMismatch { message: String, location: Option<ast::Location> },
+
+    #[error("undefined variable \"{name}\"")]
+    UndefinedVariable { name: String, location: ast::Location },
+
+    /// Functions aren't curried, so passing fewer arguments than a function
+    /// has parameters fails like passing more.
+    #[error("wrong number of arguments passed to {name}: expected {expected}, got {actual}")]
+    ArityMismatch { name: String, expected: usize, actual: usize, location: ast::Location },
+
+    #[error("division by zero: {lhs} {op} {rhs}")]
+    DivisionByZero { op: ast::BinaryOp, lhs: i32, rhs: i32 },
+
+    #[error("arithmetic overflow: {lhs} {op} {rhs}")]
+    ArithmeticOverflow { op: ast::BinaryOp, lhs: i32, rhs: i32 },
+
+    #[error("invalid shift: {lhs} {op} {rhs}, the amount must be from 0 to 31")]
+    InvalidShift { op: ast::BinaryOp, lhs: i32, rhs: i32 },
+
+    #[error("could not write the output: {0}")]
+    Output(#[from] std::io::Error),
+
+    #[error("could not read the input: {0}")]
+    Input(std::io::Error),
+
+    #[error("invalid JSON AST: {0}")]
+    InvalidAst(#[from] serde_json::Error),
+
+    #[error("stack overflow: call depth exceeded {max_depth} calling {name}")]
+    StackOverflow { name: String, max_depth: usize },
+
+    #[error("infinite recursion: {name}({arguments}) kept calling itself with the same arguments")]
+    InfiniteRecursion { name: String, arguments: String },
+
+    #[error("invalid argument passed to {name}: {message}")]
+    InvalidArgument { name: String, message: String },
+
+    #[error("a {type_name} can't be used as a key")]
+    Unhashable { type_name: &'static str },
+
+    #[error("out of fuel: the program took more than {fuel} steps")]
+    OutOfFuel { fuel: u64 },
+
+    /// Raised by the `assert` intrinsic.
+    #[error("{message}")]
+    AssertionFailed { message: String, location: ast::Location },
+
+    /// A syntax error kept in the AST as an `Error` term.
+    #[error("parse error: {message}")]
+    ParseError {
+        message: String,
+        location: ast::Location,
+        /// The tokens the parser would have accepted instead, when the
+        /// error comes from parsing source.
+        expected: Vec<String>,
+    },
+}
+
+impl InterpreterError {
+    fn type_mismatch(message: impl Into<String>) -> Self {
+        Self::TypeMismatch { message: message.into(), location: None }
+    }
+
+    fn type_mismatch_at(message: impl Into<String>, location: &ast::Location) -> Self {
+        Self::TypeMismatch { message: message.into(), location: Some(location.clone()) }
+    }
+
+    /// The name of the variant, which tools can match on.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::TypeMismatch { .. } => "TypeMismatch",
+            Self::UndefinedVariable { .. } => "UndefinedVariable",
+            Self::ArityMismatch { .. } => "ArityMismatch",
+            Self::DivisionByZero { .. } => "DivisionByZero",
+            Self::ArithmeticOverflow { .. } => "ArithmeticOverflow",
+            Self::InvalidShift { .. } => "InvalidShift",
+            Self::Output(_) => "Output",
+            Self::Input(_) => "Input",
+            Self::InvalidAst(_) => "InvalidAst",
+            Self::StackOverflow { .. } => "StackOverflow",
+            Self::InfiniteRecursion { .. } => "InfiniteRecursion",
+            Self::InvalidArgument { .. } => "InvalidArgument",
+            Self::Unhashable { .. } => "Unhashable",
+            Self::OutOfFuel { .. } => "OutOfFuel",
+            Self::AssertionFailed { .. } => "AssertionFailed",
+            Self::ParseError { .. } => "ParseError",
+        }
+    }
+
+    /// Where in the program the error happened, when it's known.
+    pub fn location(&self) -> Option<&ast::Location> {
+        match self {
+            Self::UndefinedVariable { location, .. }
+            | Self::ArityMismatch { location, .. }
+            | Self::AssertionFailed { location, .. }
+            | Self::ParseError { location, .. } => Some(location),
+            Self::TypeMismatch { location, .. } => location.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CallStack {
+    inner: Vec<Call>,
+    /// Top-level bindings, which stay visible to every frame.
+    globals: Globals,
+    /// Numbers the names of globals. It outlives [`CallStack::clear`], so
+    /// symbols handed out for one program stay valid for the next.
+    pub interner: interner::Interner,
+}
+
+#[derive(Hash, PartialEq, Eq)]
+struct MemoKey {
+    /// Functions are told apart by closure rather than by where they're
+    /// defined, since the REPL parses every line at the same offsets.
+    closure: *const Closure,
+    arguments: Vec<HashKey>,
+}
+
+/// Results of calls to pure recursive functions, keyed by the closure and
+/// its argument values.
+///
+/// Only closures whose body doesn't print, read variables it didn't bind,
+/// or call anything but itself are memoized, so that the result depends on
+/// the arguments alone.
+#[derive(Default)]
+pub struct Memo {
+    /// Whether each closure seen is pure. Holding on to the closures keeps
+    /// their addresses from being reused by new ones while keys refer to
+    /// them.
+    pure: HashMap<*const Closure, (Rc<Closure>, bool)>,
+    results: HashMap<MemoKey, RuntimeValue>,
+}
+
+impl Memo {
+    /// Builds the key of a call, or `None` when the call can't be memoized.
+    fn key(&mut self, name: &str, closure: &Rc<Closure>, slots: &[RuntimeValue]) -> Option<MemoKey> {
+        let (_, pure) = self.pure.entry(Rc::as_ptr(closure)).or_insert_with(|| {
+            let function = &closure.function;
+            let pure = function.parameters.iter().all(|x| x.text != name) && is_pure(name, &function.value);
+            (closure.clone(), pure)
+        });
+        if !*pure {
+            return None
+        }
+        // Calls with arguments that can't be keys just aren't memoized.
+        let arguments = closure.arguments(slots).iter().map(|x| HashKey::try_from(x).ok());
+        Some(MemoKey { closure: Rc::as_ptr(closure), arguments: arguments.collect::<Option<_>>()? })
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+struct LoopKey {
+    /// The closure itself rather than its function, since closures of the
+    /// same function can capture different values.
+    closure: *const Closure,
+    arguments: Vec<HashKey>,
+}
+
+/// How many times the same call has to be in progress at once, each inside
+/// the one before, for [`LoopDetector`] to report it.
+pub const LOOP_REPEATS: usize = 3;
+
+/// The calls in progress, to catch a call that keeps repeating itself at a
+/// growing depth.
+///
+/// When a body can only see its arguments, the values it captured and
+/// globals, none of which change while it runs, an identical call inside it
+/// will come around again and again. Bodies that can change something, by
+/// calling a function other than themselves or `push`, are never reported,
+/// and neither are calls with arguments that can't be keys, like arrays.
+/// Every iteration of a tail call counts as a level of depth, kept until its
+/// frame returns.
+#[derive(Default)]
+pub struct LoopDetector {
+    /// How many times each call is in progress.
+    active: HashMap<LoopKey, usize>,
+    /// Whether each closure seen can be reported, holding on to them so
+    /// that their addresses aren't reused while keys refer to them.
+    repeatable: HashMap<*const Closure, (Rc<Closure>, bool)>,
+}
+
+impl LoopDetector {
+    /// Marks a call as in progress, and returns its key to leave it with.
+    fn enter(
+        &mut self,
+        name: &str,
+        closure: &Rc<Closure>,
+        slots: &[RuntimeValue],
+        call_stack: &CallStack,
+    ) -> Result<Option<LoopKey>, InterpreterError> {
+        let (_, repeatable) = self.repeatable.entry(Rc::as_ptr(closure)).or_insert_with(|| {
+            (closure.clone(), is_repeatable(name, &closure.function.value, call_stack))
+        });
+        if !*repeatable {
+            return Ok(None)
+        }
+        let arguments = closure.arguments(slots);
+        let key = match arguments.iter().map(|x| HashKey::try_from(x).ok()).collect::<Option<_>>() {
+            Some(x) => LoopKey { closure: Rc::as_ptr(closure), arguments: x },
+            None => return Ok(None),
+        };
+        let count = self.active.entry(key.clone()).or_default();
+        *count += 1;
+        if *count >= LOOP_REPEATS {
+            let arguments: Vec<String> = arguments.iter().map(ToString::to_string).collect();
+            return Err(InterpreterError::InfiniteRecursion { name: name.to_string(), arguments: arguments.join(", ") });
+        }
+        Ok(Some(key))
+    }
+
+    fn leave(&mut self, keys: Vec<LoopKey>) {
+        for key in keys {
+            if let Some(count) = self.active.get_mut(&key) {
+                *count -= 1;
+                if *count == 0 {
+                    self.active.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+/// Tells whether a function body named `name` does the same thing every
+/// time it's called with the same arguments, printing aside. It can't call
+/// functions other than itself, and the intrinsics it calls can't change
+/// anything or read input, which rules out `push` and `read_line`.
+fn is_repeatable(name: &str, term: &ast::Term, call_stack: &CallStack) -> bool {
+    let repeatable = |x: &ast::Term| is_repeatable(name, x, call_stack);
+    match term {
+        ast::Term::Error(_) | ast::Term::Int(_) | ast::Term::Float(_) | ast::Term::Str(_) | ast::Term::Bool(_)
+        | ast::Term::Var(_) => true,
+        ast::Term::Call(x) => {
+            let callee = match &*x.callee {
+                ast::Term::Var(y) if y.text == name => true,
+                // A global of the same name would be called instead.
+                ast::Term::Var(y) if y.slot.get() == resolver::Slot::Global && !call_stack.globals.contains_key(&y.symbol.get()) =>
+                    intrinsics::INTRINSICS.iter().any(|z| z.name == y.text && !z.mutates && !z.io),
+                _ => false,
+            };
+            callee && x.arguments.iter().all(repeatable)
+        },
+        ast::Term::Binary(x) => repeatable(&x.lhs) && repeatable(&x.rhs),
+        // Closures defined in the body can't be called from it.
+        ast::Term::Function(_) => true,
+        ast::Term::Let(x) => x.name.text != name && repeatable(&x.value) && repeatable(&x.next),
+        ast::Term::If(x) => repeatable(&x.condition) && repeatable(&x.then)
+            && x.otherwise.as_deref().is_none_or(repeatable),
+        ast::Term::Print(x) => repeatable(&x.value),
+        ast::Term::First(x) => repeatable(&x.value),
+        ast::Term::Second(x) => repeatable(&x.value),
+        ast::Term::Tuple(x) => x.elements().all(repeatable),
+    }
+}
+
+/// Tells whether a function body named `name` has no effects and depends on
+/// nothing but its own frame: it doesn't print, define closures, read
+/// captured variables or globals, or call anything other than itself.
+fn is_pure(name: &str, term: &ast::Term) -> bool {
+    match term {
+        ast::Term::Error(_) | ast::Term::Print(_) | ast::Term::Function(_) => false,
+        ast::Term::Int(_) | ast::Term::Float(_) | ast::Term::Str(_) | ast::Term::Bool(_) => true,
+        // Captured variables and globals can be bound again between two
+        // calls.
+        ast::Term::Var(x) => matches!(x.slot.get(), resolver::Slot::Local(_)),
+        ast::Term::Call(x) => {
+            let calls_itself = matches!(&*x.callee, ast::Term::Var(y) if y.text == name);
+            calls_itself && x.arguments.iter().all(|y| is_pure(name, y))
+        },
+        ast::Term::Binary(x) => is_pure(name, &x.lhs) && is_pure(name, &x.rhs),
+        ast::Term::Let(x) => x.name.text != name && is_pure(name, &x.value) && is_pure(name, &x.next),
+        ast::Term::If(x) => is_pure(name, &x.condition) && is_pure(name, &x.then)
+            && x.otherwise.as_ref().is_none_or(|y| is_pure(name, y)),
+        ast::Term::First(x) => is_pure(name, &x.value),
+        ast::Term::Second(x) => is_pure(name, &x.value),
+        ast::Term::Tuple(x) => x.elements().all(|y| is_pure(name, y)),
+    }
+}
+
+/// Default value for [`Config::max_depth`].
+pub const DEFAULT_MAX_DEPTH: usize = 10000;
+
+
+impl CallStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push(&mut self, value: Call) {
+        self.inner.push(value);
+    }
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+    pub fn pop(&mut self) {
+        self.inner.pop();
+    }
+    /// Drops every frame and global, keeping the memory for the next run.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+        self.globals.clear();
+    }
+    /// Clears the stack and pushes a fresh global frame, ready to run
+    /// another program.
+    pub fn reset(&mut self) {
+        self.clear();
+        self.push(global_frame());
+    }
+    /// Reads a variable from the slot it was resolved to: the locals of the
+    /// running function, the environment its closure captured, or the
+    /// globals. Frames of the callers are never consulted.
+    pub fn get_var(&self, var: &parser::Var) -> EvalResult {
+        let value = match var.slot.get() {
+            resolver::Slot::Global => self.inner.last()
+                .and_then(|top| top.globals.as_ref()?.get(&var.symbol.get()))
+                .or_else(|| self.globals.get(&var.symbol.get())),
+            resolver::Slot::Local(index) => self.inner.last().and_then(|top| top.slots.get(index)),
+            resolver::Slot::Captured { depth, index } =>
+                self.inner.last().and_then(|top| top.env.as_ref()?.get(depth, index)),
+        };
+        value
+            .cloned()
+            .ok_or_else(|| InterpreterError::UndefinedVariable { name: var.text.to_string(), location: var.location.clone() })
+    }
+    /// Snapshots the bindings of the top frame for a closure defined in it.
+    /// Closures defined in the global frame capture no slots.
+    pub fn capture(&self) -> Option<Rc<Env>> {
+        let stack = &self.inner;
+        if stack.len() <= 1 {
+            return None
+        }
+        let top = &stack[stack.len() - 1];
+        Some(Rc::new(Env {
+            vars: top.slots.to_vec(),
+            parent: top.env.clone(),
+        }))
+    }
+    /// The globals seen by a closure defined in the top frame. Closures
+    /// defined in the global frame snapshot them, so a global bound again
+    /// later doesn't change what they see; names bound after them are still
+    /// found among the globals.
+    pub fn capture_globals(&self) -> Rc<Globals> {
+        match self.inner.last().and_then(|top| top.globals.clone()) {
+            Some(x) => x,
+            None => self.snapshot_globals(),
+        }
+    }
+    /// Copies the globals as they are now.
+    fn snapshot_globals(&self) -> Rc<Globals> {
+        Rc::new(self.globals.clone())
+    }
+    /// Replaces the bindings of the top frame, used when a tail call
+    /// reuses it.
+    pub fn replace_slots(&mut self, slots: Vec<RuntimeValue>) {
+        if let Some(top) = self.inner.last_mut() {
+            top.slots = slots;
+        }
+    }
+    /// The top-level bindings, sorted by name, so the order doesn't depend
+    /// on how they're stored or the order they were bound in:
+    ///
+    /// ```
+    /// use caramuru::{CallStack, RuntimeValue};
+    ///
+    /// let mut stack = CallStack::new();
+    /// for name in ["zeta", "alpha", "mid"] {
+    ///     stack.set_global(name, RuntimeValue::Int(1));
+    /// }
+    /// let names: Vec<_> = stack.globals().into_iter().map(|(x, _)| x).collect();
+    /// assert_eq!(names, ["alpha", "mid", "zeta"]);
+    /// ```
+    pub fn globals(&self) -> Vec<(&str, &RuntimeValue)> {
+        // Bindings in blocks of the top-level code are stored with the
+        // globals, but under symbols of their own that are left out here.
+        let mut globals: Vec<_> = self.globals.iter()
+            .filter(|(x, _)| self.interner.is_interned(**x))
+            .map(|(x, y)| (self.interner.name(*x), y))
+            .collect();
+        globals.sort_by_key(|(x, _)| *x);
+        globals
+    }
+    /// Binds a top-level name, as if by a `let` before the program.
+    pub fn set_global(&mut self, name: &str, value: RuntimeValue) {
+        let symbol = self.interner.intern(name);
+        self.globals.insert(symbol, value);
+    }
+    /// Binds a `let` in the slot it was resolved to.
+    pub fn set_var(&mut self, var: &parser::Var, value: RuntimeValue) {
+        match var.slot.get() {
+            resolver::Slot::Global => {
+                self.globals.insert(var.symbol.get(), value);
+            },
+            resolver::Slot::Local(index) => {
+                if let Some(x) = self.inner.last_mut().and_then(|top| top.slots.get_mut(index)) {
+                    *x = value;
+                }
+            },
+            resolver::Slot::Captured { .. } => unreachable!("let bindings are never resolved to a captured slot"),
+        }
+    }
+}
+
+/// A value at runtime. Cloning copies tuples deeply, but shares arrays,
+/// closures and thunks.
+///
+/// ```
+/// let value = caramuru::eval_str("(1, fn (x) => { x })").unwrap();
+/// assert_eq!(format!("{value:?}"), "Tuple([Int(1), Function { .. }])");
+/// assert_ne!(value, caramuru::eval_str("(1, fn (x) => { x })").unwrap());
+///
+/// use caramuru::RuntimeValue::{Int, Void};
+/// assert_eq!(Void(()), Void(()));
+/// assert_ne!(Void(()), Int(0));
+/// assert_eq!(Void(()).to_string(), "");
+/// ```
+#[derive(Clone)]
+pub enum RuntimeValue {
+    Int(i32),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    Tuple(Vec<RuntimeValue>),
+    /// A growable list, made by the `array` intrinsic. Arrays are shared
+    /// rather than copied: `push` changes the array for every binding of it.
+    Array(Rc<std::cell::RefCell<Vec<RuntimeValue>>>),
+    Function(Rc<Closure>),
+    /// An argument passed by need, only found in the slots of a frame.
+    Thunk(Rc<Thunk>),
+    /// No value, like the result of an `if` without an else branch whose
+    /// condition is false. It's only equal to itself, prints as nothing,
+    /// and joins strings as the empty one.
+    Void(())
+}
+
+/// An argument that hasn't been evaluated yet, with the scope of the call
+/// it comes from. It is evaluated the first time it's read, and keeps the
+/// value for the later reads.
+pub struct Thunk {
+    term: ast::Term,
+    /// The frame of the caller and the environment it had captured, or
+    /// `None` for calls in top-level code.
+    scope: Option<Rc<Env>>,
+    /// The globals as the caller saw them.
+    globals: Rc<Globals>,
+    value: std::cell::RefCell<Option<RuntimeValue>>,
+}
+
+impl RuntimeValue {
+    /// The name of the type of the value, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RuntimeValue::Int(_) => "int",
+            RuntimeValue::Float(_) => "float",
+            RuntimeValue::Str(_) => "str",
+            RuntimeValue::Bool(_) => "bool",
+            RuntimeValue::Tuple(_) => "tuple",
+            RuntimeValue::Array(_) => "array",
+            RuntimeValue::Function(_) => "function",
+            RuntimeValue::Thunk(_) => "thunk",
+            RuntimeValue::Void(_) => "void",
+        }
+    }
+
+    fn expected(&self, type_name: &str) -> InterpreterError {
+        InterpreterError::type_mismatch(format!("expected {type_name}, found {}", self.type_name()))
+    }
+
+    /// Reads an int, failing with [`InterpreterError::TypeMismatch`] for any
+    /// other value, as do the other accessors.
+    ///
+    /// ```
+    /// use caramuru::RuntimeValue;
+    ///
+    /// let value = caramuru::eval_str("(1 + 2, (\"three\", true))").unwrap();
+    /// let [n, rest] = value.as_tuple().unwrap() else { panic!() };
+    /// assert_eq!(n.as_int().unwrap(), 3);
+    /// assert_eq!(rest.as_tuple().unwrap()[0].as_str().unwrap(), "three");
+    /// assert!(rest.as_tuple().unwrap()[1].as_bool().unwrap());
+    ///
+    /// let error = RuntimeValue::Bool(true).as_int().unwrap_err();
+    /// assert_eq!(error.to_string(), "type mismatch: expected an int, found bool");
+    /// assert!(n.as_str().is_err() && n.as_bool().is_err() && n.as_tuple().is_err());
+    /// ```
+    pub fn as_int(&self) -> Result<i32, InterpreterError> {
+        match self {
+            RuntimeValue::Int(x) => Ok(*x),
+            x => Err(x.expected("an int")),
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, InterpreterError> {
+        match self {
+            RuntimeValue::Str(x) => Ok(x),
+            x => Err(x.expected("a str")),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, InterpreterError> {
+        match self {
+            RuntimeValue::Bool(x) => Ok(*x),
+            x => Err(x.expected("a bool")),
+        }
+    }
+
+    /// Reads the elements of a tuple, pairs included.
+    pub fn as_tuple(&self) -> Result<&[RuntimeValue], InterpreterError> {
+        match self {
+            RuntimeValue::Tuple(x) => Ok(x),
+            x => Err(x.expected("a tuple")),
+        }
+    }
+}
+
+/// Closures and thunks print without their contents, which would drag in
+/// whole syntax trees and environments.
+impl std::fmt::Debug for RuntimeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeValue::Int(x) => f.debug_tuple("Int").field(x).finish(),
+            RuntimeValue::Float(x) => f.debug_tuple("Float").field(x).finish(),
+            RuntimeValue::Str(x) => f.debug_tuple("Str").field(x).finish(),
+            RuntimeValue::Bool(x) => f.debug_tuple("Bool").field(x).finish(),
+            RuntimeValue::Tuple(x) => f.debug_tuple("Tuple").field(x).finish(),
+            RuntimeValue::Array(x) => f.debug_tuple("Array").field(&*x.borrow()).finish(),
+            RuntimeValue::Function(_) => f.debug_struct("Function").finish_non_exhaustive(),
+            RuntimeValue::Thunk(_) => f.debug_struct("Thunk").finish_non_exhaustive(),
+            RuntimeValue::Void(_) => write!(f, "Void"),
+        }
+    }
+}
+
+/// Compares two values structurally, recursing into tuples and arrays, as
+/// the `==` operator does. Numbers are compared after promotion, so
+/// `Int(1)` equals `Float(1.0)`. Functions are equal only when they are the
+/// same closure, since there's no telling whether two functions compute the
+/// same thing. Values of different types are never equal.
+impl PartialEq for RuntimeValue {
+    fn eq(&self, other: &Self) -> bool {
+        if let Some((l, r)) = float_operands(self, other) {
+            return l == r
+        }
+        match (self, other) {
+            (RuntimeValue::Int(l), RuntimeValue::Int(r)) => l == r,
+            (RuntimeValue::Str(l), RuntimeValue::Str(r)) => l == r,
+            (RuntimeValue::Bool(l), RuntimeValue::Bool(r)) => l == r,
+            (RuntimeValue::Tuple(l), RuntimeValue::Tuple(r)) => l == r,
+            (RuntimeValue::Array(l), RuntimeValue::Array(r)) => l == r,
+            (RuntimeValue::Function(l), RuntimeValue::Function(r)) => Rc::ptr_eq(l, r),
+            (RuntimeValue::Void(_), RuntimeValue::Void(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A value that can be hashed, for use as a `HashMap` key.
+///
+/// Integers, strings, booleans and tuples made of those are hashable.
+/// Functions have no meaningful identity to hash, floats aren't equal to
+/// themselves when NaN, and arrays can change after being hashed, so
+/// converting those fails with [`InterpreterError::Unhashable`], as does
+/// `Void`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum HashKey {
+    Int(i32),
+    Str(String),
+    Bool(bool),
+    Tuple(Vec<HashKey>),
+}
+
+impl TryFrom<&RuntimeValue> for HashKey {
+    type Error = InterpreterError;
+
+    fn try_from(value: &RuntimeValue) -> Result<Self, Self::Error> {
+        match value {
+            RuntimeValue::Int(x) => Ok(HashKey::Int(*x)),
+            RuntimeValue::Str(x) => Ok(HashKey::Str(x.to_string())),
+            RuntimeValue::Bool(x) => Ok(HashKey::Bool(*x)),
+            RuntimeValue::Tuple(x) => Ok(HashKey::Tuple(x.iter().map(HashKey::try_from).collect::<Result<_, _>>()?)),
+            _ => Err(InterpreterError::Unhashable { type_name: value.type_name() }),
+        }
+    }
+}
+
+impl std::fmt::Display for RuntimeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeValue::Int(x) => write!(f, "{x}"),
+            // Whole floats print without a fractional part, like `3`.
+            RuntimeValue::Float(x) => write!(f, "{x}"),
+            RuntimeValue::Str(x) => write!(f, "{x}"),
+            RuntimeValue::Bool(x) => write!(f, "{x}"),
+            RuntimeValue::Tuple(x) => {
+                write!(f, "(")?;
+                for (i, y) in x.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{y}")?;
+                }
+                write!(f, ")")
+            },
+            RuntimeValue::Array(x) => {
+                write!(f, "[")?;
+                for (i, y) in x.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{y}")?;
+                }
+                write!(f, "]")
+            },
+            RuntimeValue::Function(_) => write!(f, "<#closure>"),
+            RuntimeValue::Thunk(x) => match &*x.value.borrow() {
+                Some(y) => write!(f, "{y}"),
+                None => write!(f, "<#thunk>"),
+            },
+            RuntimeValue::Void(_) => Ok(()),
+        }
+    }
+}
+
+/// Outcome of evaluating a function body in tail position.
+enum Tail {
+    /// The body produced its final value.
+    Value(RuntimeValue),
+    /// The body ended in a call to the function itself, with the slots
+    /// for the next iteration.
+    Call(Vec<RuntimeValue>),
+}
+
+/// Concatenates two operands of `+` when at least one of them is a string,
+/// coercing numbers to their decimal form and void to the empty string.
+fn concat_operands(l: &RuntimeValue, r: &RuntimeValue) -> Result<Option<String>, InterpreterError> {
+    let as_text = |x: &RuntimeValue| match x {
+        RuntimeValue::Str(x) => Ok(x.to_string()),
+        RuntimeValue::Int(x) => Ok(x.to_string()),
+        RuntimeValue::Float(x) => Ok(x.to_string()),
+        RuntimeValue::Void(_) => Ok(String::new()),
+        _ => Err(InterpreterError::type_mismatch("operand is not a string or a number")),
+    };
+    match (l, r) {
+        (RuntimeValue::Str(_), _) | (_, RuntimeValue::Str(_)) => Ok(Some(as_text(l)? + &as_text(r)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Reads an operand of `&&`/`||`, where integers count as true when nonzero.
+fn as_logical(value: &RuntimeValue) -> Result<bool, InterpreterError> {
+    value.as_bool()
+        .or_else(|_| value.as_int().map(|x| x != 0))
+        .map_err(|_| InterpreterError::type_mismatch("logical operand is not a boolean or an integer"))
+}
+
+/// Reads the condition of an `if`. Only booleans are accepted, unless
+/// `lenient` lets nonzero integers count as true.
+fn as_condition(value: RuntimeValue, lenient: bool) -> Result<bool, InterpreterError> {
+    match value {
+        RuntimeValue::Bool(x) => Ok(x),
+        RuntimeValue::Int(x) if lenient => Ok(x != 0),
+        x => Err(x.expected("a bool condition")),
+    }
+}
+
+/// Reads the operands of an arithmetic or comparison operator as floats
+/// when at least one of them is a float, promoting the integer one.
+fn float_operands(l: &RuntimeValue, r: &RuntimeValue) -> Option<(f64, f64)> {
+    match (l, r) {
+        (RuntimeValue::Float(l), RuntimeValue::Float(r)) => Some((*l, *r)),
+        (RuntimeValue::Int(l), RuntimeValue::Float(r)) => Some((*l as f64, *r)),
+        (RuntimeValue::Float(l), RuntimeValue::Int(r)) => Some((*l, *r as f64)),
+        _ => None,
+    }
+}
+
+/// Applies an operator to float operands. Division by zero follows IEEE 754
+/// and yields an infinity or NaN instead of an error.
+fn eval_float_op(op: ast::BinaryOp, l: f64, r: f64) -> EvalResult {
+    Ok(match op {
+        ast::BinaryOp::Add => RuntimeValue::Float(l + r),
+        ast::BinaryOp::Sub => RuntimeValue::Float(l - r),
+        ast::BinaryOp::Mul => RuntimeValue::Float(l * r),
+        ast::BinaryOp::Div => RuntimeValue::Float(l / r),
+        ast::BinaryOp::Rem => RuntimeValue::Float(l % r),
+        ast::BinaryOp::Eq => RuntimeValue::Bool(l == r),
+        ast::BinaryOp::Neq => RuntimeValue::Bool(l != r),
+        ast::BinaryOp::Lt => RuntimeValue::Bool(l < r),
+        ast::BinaryOp::Gt => RuntimeValue::Bool(l > r),
+        ast::BinaryOp::Lte => RuntimeValue::Bool(l <= r),
+        ast::BinaryOp::Gte => RuntimeValue::Bool(l >= r),
+        ast::BinaryOp::And | ast::BinaryOp::Or | ast::BinaryOp::BitAnd | ast::BinaryOp::BitOr
+        | ast::BinaryOp::BitXor | ast::BinaryOp::Shl | ast::BinaryOp::Shr =>
+            unreachable!("logical and bitwise operators are not float operations"),
+    })
+}
+
+fn eval_binary_op(op: ast::BinaryOp, l: RuntimeValue, r: RuntimeValue) -> EvalResult {
+    if let ast::BinaryOp::Add = op {
+        if let Some(x) = concat_operands(&l, &r)? {
+            return Ok(RuntimeValue::Str(x));
+        }
+    }
+    // Bitwise operators only take integers, and fail below on anything else.
+    let bitwise = matches!(
+        op,
+        ast::BinaryOp::BitAnd | ast::BinaryOp::BitOr | ast::BinaryOp::BitXor | ast::BinaryOp::Shl | ast::BinaryOp::Shr
+    );
+    if !bitwise && !matches!(op, ast::BinaryOp::And | ast::BinaryOp::Or) {
+        if let Some((l, r)) = float_operands(&l, &r) {
+            return eval_float_op(op, l, r);
+        }
+    }
+    match (&op, &l, &r) {
+        (ast::BinaryOp::And, l, r) => return Ok(RuntimeValue::Bool(as_logical(l)? && as_logical(r)?)),
+        (ast::BinaryOp::Or, l, r) => return Ok(RuntimeValue::Bool(as_logical(l)? || as_logical(r)?)),
+        // Equality is defined on every value, see `PartialEq for RuntimeValue`.
+        (ast::BinaryOp::Eq, l, r) => return Ok(RuntimeValue::Bool(l == r)),
+        (ast::BinaryOp::Neq, l, r) => return Ok(RuntimeValue::Bool(l != r)),
+        // Strings are ordered by their bytes, which is lexicographic order.
+        (ast::BinaryOp::Lt, RuntimeValue::Str(l), RuntimeValue::Str(r)) => return Ok(RuntimeValue::Bool(l < r)),
+        (ast::BinaryOp::Gt, RuntimeValue::Str(l), RuntimeValue::Str(r)) => return Ok(RuntimeValue::Bool(l > r)),
+        (ast::BinaryOp::Lte, RuntimeValue::Str(l), RuntimeValue::Str(r)) => return Ok(RuntimeValue::Bool(l <= r)),
+        (ast::BinaryOp::Gte, RuntimeValue::Str(l), RuntimeValue::Str(r)) => return Ok(RuntimeValue::Bool(l >= r)),
+        _ => {}
+    }
+    let (l, r) = match (l.as_int(), r.as_int()) {
+        (Ok(l), Ok(r)) => (l, r),
+        _ if matches!(op, ast::BinaryOp::Lt | ast::BinaryOp::Gt | ast::BinaryOp::Lte | ast::BinaryOp::Gte) =>
+            return Err(InterpreterError::type_mismatch(format!("operator {op} expects two numbers or two strings"))),
+        _ => return Err(InterpreterError::type_mismatch(format!("operator {op} expects integer operands"))),
+    };
+    if r == 0 {
+        if let ast::BinaryOp::Div | ast::BinaryOp::Rem = op {
+            return Err(InterpreterError::DivisionByZero { op, lhs: l, rhs: r });
+        }
+    }
+    let result = match op {
+        ast::BinaryOp::Add => l.checked_add(r),
+        ast::BinaryOp::Sub => l.checked_sub(r),
+        ast::BinaryOp::Mul => l.checked_mul(r),
+        ast::BinaryOp::Div => l.checked_div(r),
+        ast::BinaryOp::Rem => l.checked_rem(r),
+        ast::BinaryOp::Lt => return Ok(RuntimeValue::Bool(l < r)),
+        ast::BinaryOp::Gt => return Ok(RuntimeValue::Bool(l > r)),
+        ast::BinaryOp::Lte => return Ok(RuntimeValue::Bool(l <= r)),
+        ast::BinaryOp::Gte => return Ok(RuntimeValue::Bool(l >= r)),
+        ast::BinaryOp::BitAnd => Some(l & r),
+        ast::BinaryOp::BitOr => Some(l | r),
+        ast::BinaryOp::BitXor => Some(l ^ r),
+        // Bits shifted out are dropped, but the amount has to fit the width.
+        ast::BinaryOp::Shl | ast::BinaryOp::Shr => {
+            let shifted = u32::try_from(r).ok().and_then(|x| match op {
+                ast::BinaryOp::Shl => l.checked_shl(x),
+                _ => l.checked_shr(x),
+            });
+            return shifted.map(RuntimeValue::Int).ok_or(InterpreterError::InvalidShift { op, lhs: l, rhs: r })
+        },
+        ast::BinaryOp::And | ast::BinaryOp::Or | ast::BinaryOp::Eq | ast::BinaryOp::Neq =>
+            unreachable!("logical and equality operators are handled above"),
+    };
+    // Overflow is an error in every build profile instead of wrapping in release.
+    result.map(RuntimeValue::Int).ok_or(InterpreterError::ArithmeticOverflow { op, lhs: l, rhs: r })
+}
+
+/// A call that an error returned through.
+#[derive(Debug)]
+pub struct BacktraceFrame {
+    /// The variable the function was called through.
+    pub name: String,
+    /// Where the call is.
+    pub location: ast::Location,
+}
+
+/// Settings that change how programs are evaluated.
+#[derive(Clone)]
+pub struct Config {
+    /// How many frames can be pushed before calls fail with
+    /// [`InterpreterError::StackOverflow`].
+    pub max_depth: usize,
+    /// Whether the results of pure recursive functions are cached.
+    pub memoize: bool,
+    /// Whether every call and its result are logged to stderr.
+    pub trace: bool,
+    /// Whether [`Stats`] are collected.
+    pub stats: bool,
+    /// Whether the time spent in each function is measured, in a [`Profile`].
+    pub profile: bool,
+    /// Whether calls that keep calling themselves with the same arguments
+    /// fail with [`InterpreterError::InfiniteRecursion`].
+    pub detect_loops: bool,
+    /// Whether programs can read environment variables with `get_env`.
+    pub env: bool,
+    /// Whether programs can read from stdin with `read_line`. It's off by
+    /// default, so that sandboxed runs don't block waiting for input.
+    pub io: bool,
+    /// Whether integers are accepted as `if` conditions, nonzero ones
+    /// being true.
+    pub lenient: bool,
+    /// Whether arguments are passed by need, as [`Thunk`]s, rather than
+    /// evaluated before the call.
+    pub lazy: bool,
+    /// How many steps a program can take before failing with
+    /// [`InterpreterError::OutOfFuel`]. A step is a term evaluated, or an
+    /// instruction run on the VM.
+    pub fuel: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            memoize: false,
+            trace: false,
+            stats: false,
+            profile: false,
+            detect_loops: false,
+            env: true,
+            io: false,
+            lenient: false,
+            lazy: false,
+            fuel: None,
+        }
+    }
+}
+
+/// Counters of the work done by an interpreter.
+#[derive(Default, Debug)]
+pub struct Stats {
+    /// Function calls, including the iterations of tail calls.
+    pub calls: usize,
+    /// Terms evaluated.
+    pub evals: usize,
+    /// The deepest nesting of function calls.
+    pub max_depth: usize,
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "calls: {}", self.calls)?;
+        writeln!(f, "evaluated terms: {}", self.evals)?;
+        write!(f, "max depth: {}", self.max_depth)
+    }
+}
+
+/// The time spent in the calls to a function.
+#[derive(Default, Debug)]
+pub struct FunctionTime {
+    /// Calls made, a loop of tail calls counting as one.
+    pub calls: usize,
+    /// Time spent in the function itself, leaving out the functions it called.
+    pub own: std::time::Duration,
+    /// Time from entering the function until it returned, counting the
+    /// calls it made. Recursive calls aren't counted twice.
+    pub inclusive: std::time::Duration,
+}
+
+/// A call being timed.
+struct ProfileFrame {
+    name: String,
+    start: std::time::Instant,
+    /// Time spent in the functions this one called, so far.
+    callees: std::time::Duration,
+}
+
+/// Wall-clock time spent in each function, by the name it was called
+/// through.
+#[derive(Default)]
+pub struct Profile {
+    /// Sorted by name, so iterating it gives the same order on every run.
+    pub functions: BTreeMap<String, FunctionTime>,
+    /// The calls in progress, innermost last, like the frames they run in.
+    frames: Vec<ProfileFrame>,
+    /// How many calls to each function are in progress.
+    active: HashMap<String, usize>,
+}
+
+impl Profile {
+    fn enter(&mut self, name: &str) {
+        self.frames.push(ProfileFrame { name: name.to_string(), start: std::time::Instant::now(), callees: Default::default() });
+        *self.active.entry(name.to_string()).or_default() += 1;
+    }
+
+    fn leave(&mut self) {
+        let Some(frame) = self.frames.pop() else { return };
+        let elapsed = frame.start.elapsed();
+        if let Some(caller) = self.frames.last_mut() {
+            caller.callees += elapsed;
+        }
+        let active = self.active.entry(frame.name.to_string()).or_default();
+        *active -= 1;
+        let outermost = *active == 0;
+        let time = self.functions.entry(frame.name).or_default();
+        time.calls += 1;
+        time.own += elapsed.saturating_sub(frame.callees);
+        if outermost {
+            time.inclusive += elapsed;
+        }
+    }
+}
+
+impl std::fmt::Display for Profile {
+    /// A table of the functions, the ones taking the most time on their own
+    /// first.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut functions: Vec<_> = self.functions.iter().collect();
+        functions.sort_by(|(x, a), (y, b)| b.own.cmp(&a.own).then(x.cmp(y)));
+        let width = functions.iter().map(|(x, _)| x.len()).chain(["function".len()]).max().unwrap_or_default();
+        write!(f, "{:width$} {:>10} {:>12} {:>12}", "function", "calls", "own", "inclusive")?;
+        for (name, time) in functions {
+            let own = format!("{:.3}ms", time.own.as_secs_f64() * 1000.0);
+            let inclusive = format!("{:.3}ms", time.inclusive.as_secs_f64() * 1000.0);
+            write!(f, "\n{name:width$} {:>10} {own:>12} {inclusive:>12}", time.calls)?;
+        }
+        Ok(())
+    }
+}
+
+/// Evaluates terms, owning the state shared by every call: the call stack,
+/// where `print` writes to and the memoized results.
+pub struct Interpreter {
+    pub call_stack: CallStack,
+    /// Where `print` writes to. It must be flushed before the program
+    /// exits, since the default one buffers stdout.
+    pub output: Box<dyn Write>,
+    /// Where `read_line` reads from, or `None` for stdin. Stdin isn't held
+    /// locked, so the REPL can keep reading it between programs.
+    ///
+    /// ```
+    /// use caramuru::{parser, Config, Interpreter, RuntimeValue};
+    ///
+    /// let config = Config { io: true, ..Config::default() };
+    /// let mut interpreter = Interpreter::with_output(config, Box::new(std::io::sink()));
+    /// interpreter.input = Some(Box::new(std::io::Cursor::new(&b"first\nsecond\n"[..])));
+    /// interpreter.reset();
+    /// let file = parser::parse_or_report("input.rinha", "let a = read_line(); let b = read_line(); (a, b, read_line())").unwrap();
+    /// let value = interpreter.eval(&file.expression).unwrap();
+    /// assert_eq!(value.to_string(), "(first, second, )");
+    /// assert_eq!(value.as_tuple().unwrap()[2], RuntimeValue::Void(()));
+    /// ```
+    pub input: Option<Box<dyn BufRead>>,
+    pub config: Config,
+    /// Cached results of pure functions, when memoization is enabled.
+    memo: Option<Memo>,
+    /// The calls in progress, when loop detection is enabled.
+    loops: Option<LoopDetector>,
+    /// Execution statistics, when enabled.
+    pub stats: Option<Stats>,
+    /// Time spent in each function, when profiling.
+    pub profile: Option<Profile>,
+    /// The steps left before running out of fuel, when there's a budget.
+    pub fuel: Option<u64>,
+    /// The calls that the last error was raised in and returned through,
+    /// innermost first.
+    pub backtrace: Vec<BacktraceFrame>,
+}
+
+impl Interpreter {
+    /// Creates an interpreter that prints to a buffered stdout.
+    pub fn new(config: Config) -> Self {
+        Self::with_output(config, Box::new(BufWriter::new(std::io::stdout())))
+    }
+
+    /// Creates an interpreter whose `print` output goes to `output`.
+    pub fn with_output(config: Config, output: Box<dyn Write>) -> Self {
+        Self {
+            call_stack: CallStack::new(),
+            output,
+            input: None,
+            memo: config.memoize.then(Memo::default),
+            loops: config.detect_loops.then(LoopDetector::default),
+            stats: config.stats.then(Stats::default),
+            profile: config.profile.then(Profile::default),
+            backtrace: vec![],
+            fuel: config.fuel,
+            config,
+        }
+    }
+
+    /// Forgets everything left by previous programs, so the next one runs
+    /// as if on a new interpreter with the same output.
+    ///
+    /// ```
+    /// use caramuru::{parser, Config, Interpreter};
+    ///
+    /// let mut interpreter = Interpreter::with_output(Config::default(), Box::new(std::io::sink()));
+    /// interpreter.reset();
+    /// let first = parser::parse_or_report("first.rinha", "let x = 1; x").unwrap();
+    /// interpreter.eval(&first.expression).unwrap();
+    ///
+    /// interpreter.reset();
+    /// let second = parser::parse_or_report("second.rinha", "x").unwrap();
+    /// assert!(interpreter.eval(&second.expression).is_err());
+    /// ```
+    pub fn reset(&mut self) {
+        self.call_stack.reset();
+        self.memo = self.config.memoize.then(Memo::default);
+        self.loops = self.config.detect_loops.then(LoopDetector::default);
+        self.stats = self.config.stats.then(Stats::default);
+        self.profile = self.config.profile.then(Profile::default);
+        self.fuel = self.config.fuel;
+    }
+
+    /// Takes a step out of the fuel budget, if there is one.
+    fn burn_fuel(&mut self) -> Result<(), InterpreterError> {
+        match &mut self.fuel {
+            Some(0) => Err(InterpreterError::OutOfFuel { fuel: self.config.fuel.unwrap_or_default() }),
+            Some(x) => {
+                *x -= 1;
+                Ok(())
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Evaluates the arguments of a call into the slots of a new frame for
+    /// the called function.
+    fn bind_arguments(
+        &mut self,
+        callee: &parser::Var,
+        closure: &Rc<Closure>,
+        arguments: &[ast::Term],
+    ) -> Result<Vec<RuntimeValue>, InterpreterError> {
+        let function = &closure.function;
+        if arguments.len() != function.parameters.len() {
+            return Err(InterpreterError::ArityMismatch {
+                name: callee.text.to_string(),
+                expected: function.parameters.len(),
+                actual: arguments.len(),
+                location: callee.location.clone(),
+            });
+        }
+        let mut slots = Vec::with_capacity(function.frame_size.get());
+        if closure.name.is_some() {
+            slots.push(RuntimeValue::Function(closure.clone()));
+        }
+        if self.config.lazy {
+            let scope = self.call_stack.capture();
+            let globals = self.call_stack.capture_globals();
+            for arg in arguments {
+                let val = match arg {
+                    // Literals are as cheap to evaluate as to wrap.
+                    ast::Term::Int(_) | ast::Term::Float(_) | ast::Term::Str(_) | ast::Term::Bool(_) => self.eval_term(arg)?,
+                    _ => RuntimeValue::Thunk(Rc::new(Thunk {
+                        term: arg.clone(),
+                        scope: scope.clone(),
+                        globals: globals.clone(),
+                        value: Default::default(),
+                    })),
+                };
+                slots.push(val);
+            }
+        } else {
+            for arg in arguments {
+                let val = self.eval_term(arg)?;
+                slots.push(val);
+            }
+        }
+        // The `let` slots are filled as the body runs.
+        slots.resize_with(slots.len().max(function.frame_size.get()), || RuntimeValue::Void(()));
+        Ok(slots)
+    }
+
+    /// Evaluates a thunk read from a variable, in the frame of the call it
+    /// was passed to, unless it already was. Other values are returned as
+    /// they are.
+    fn force(&mut self, value: RuntimeValue) -> EvalResult {
+        let thunk = match value {
+            RuntimeValue::Thunk(x) => x,
+            x => return Ok(x),
+        };
+        if let Some(x) = &*thunk.value.borrow() {
+            return Ok(x.clone())
+        }
+        // Thunks of tail calls can nest one in another for as long as the
+        // loop runs, so forcing them counts towards the depth limit.
+        if self.call_stack.len() > self.config.max_depth {
+            let name = "a lazy argument".to_string();
+            return Err(InterpreterError::StackOverflow { name, max_depth: self.config.max_depth });
+        }
+        self.call_stack.push(Call {
+            slots: thunk.scope.as_ref().map_or_else(Vec::new, |x| x.vars.to_vec()),
+            env: thunk.scope.as_ref().and_then(|x| x.parent.clone()),
+            globals: Some(thunk.globals.clone()),
+        });
+        let result = self.eval_term(&thunk.term);
+        self.call_stack.pop();
+        let value = result?;
+        *thunk.value.borrow_mut() = Some(value.clone());
+        Ok(value)
+    }
+
+    /// Evaluates the body of `function`, stopping at a call to itself in tail
+    /// position so that the caller can run it as a loop on the same frame.
+    fn eval_tail(&mut self, name: &str, closure: &Rc<Closure>, expr: &ast::Term) -> Result<Tail, InterpreterError> {
+        // A loop of tail calls may not evaluate any term outside of this.
+        self.burn_fuel()?;
+        match expr {
+            ast::Term::Let(x) => {
+                let next = self.bind_lets(x)?;
+                self.eval_tail(name, closure, next)
+            },
+            ast::Term::If(x) => {
+                let condition = as_condition(self.eval_term(&x.condition)?, self.config.lenient)?;
+                if condition { self.eval_tail(name, closure, &x.then) }
+                else {
+                    match &x.otherwise {
+                        Some(otherwise) => self.eval_tail(name, closure, otherwise),
+                        None => Ok(Tail::Value(RuntimeValue::Void(()))),
+                    }
+                }
+            },
+            ast::Term::Call(ast::Call { callee, arguments, .. }) => match &**callee {
+                ast::Term::Var(x) if x.text == name => match self.call_stack.get_var(x)? {
+                    RuntimeValue::Function(y) if Rc::ptr_eq(&y, closure) =>
+                        Ok(Tail::Call(self.bind_arguments(x, closure, arguments)?)),
+                    _ => Ok(Tail::Value(self.call_fn(callee, arguments)?)),
+                },
+                callee => Ok(Tail::Value(self.call_fn(callee, arguments)?)),
+            },
+            expr => Ok(Tail::Value(self.eval_term(expr)?)),
+        }
+    }
+
+    /// Logs a call and its arguments to stderr when tracing is enabled.
+    fn trace_call(&self, indent: &str, name: &str, closure: &Closure, slots: &[RuntimeValue]) {
+        if !self.config.trace {
+            return
+        }
+        let arguments: Vec<String> = closure.arguments(slots).iter().map(|x| x.to_string()).collect();
+        eprintln!("{indent}{name}({})", arguments.join(", "));
+    }
+
+    fn call_intrinsic(
+        &mut self,
+        intrinsic: &intrinsics::Intrinsic,
+        callee: &parser::Var,
+        arguments: &[ast::Term],
+    ) -> EvalResult {
+        if intrinsic.arity.is_some_and(|x| x != arguments.len()) {
+            return Err(InterpreterError::ArityMismatch {
+                name: intrinsic.name.to_string(),
+                expected: intrinsic.arity.unwrap_or_default(),
+                actual: arguments.len(),
+                location: callee.location.clone(),
+            });
+        }
+        let values = arguments.iter().map(|x| self.eval_term(x)).collect::<Result<_, _>>()?;
+        (intrinsic.call)(values, &callee.location, self)
+    }
+
+    pub fn call_fn(&mut self, callee: &ast::Term, arguments: &[ast::Term]) -> EvalResult {
+        // Any other callee is evaluated like a term, so that a function can be
+        // called right where it's defined.
+        let (callee, value) = match callee {
+            ast::Term::Var(x) => (std::borrow::Cow::Borrowed(x), self.call_stack.get_var(x).and_then(|x| self.force(x))),
+            term => (std::borrow::Cow::Owned(parser::Var::anonymous(ast::Element::location(term))), self.eval_term(term)),
+        };
+        let anonymous = matches!(callee, std::borrow::Cow::Owned(_));
+        let callee = &*callee;
+        let name = callee.text.to_string();
+        let closure = match value {
+            Ok(RuntimeValue::Function(x)) => x,
+            Ok(x) => return Err(InterpreterError::type_mismatch(match anonymous {
+                true => format!("callee is not a function, found {}", x.type_name()),
+                false => format!("\"{name}\" is not a function"),
+            })),
+            Err(e) => match intrinsics::lookup(&name, &self.config) {
+                Some(intrinsic) => return self.call_intrinsic(intrinsic, callee, arguments),
+                None => return Err(e),
+            },
+        };
+        let slots = self.bind_arguments(callee, &closure, arguments)?;
+        let memo_key = match &mut self.memo {
+            Some(memo) => {
+                let key = memo.key(&name, &closure, &slots);
+                if let Some(x) = key.as_ref().and_then(|key| memo.results.get(key)) {
+                    return Ok(x.clone())
+                }
+                key
+            },
+            None => None,
+        };
+        if self.call_stack.len() > self.config.max_depth {
+            return Err(InterpreterError::StackOverflow { name, max_depth: self.config.max_depth });
+        }
+        // Tail calls turn this frame into a call that is still in progress,
+        // so it leaves every one of them when it returns.
+        let mut loop_keys = vec![];
+        if let Some(loops) = &mut self.loops {
+            loop_keys.extend(loops.enter(&name, &closure, &slots, &self.call_stack)?);
+        }
+        // Calls made from the global frame are not indented.
+        let indent = "  ".repeat(self.call_stack.len().saturating_sub(1));
+        self.trace_call(&indent, &name, &closure, &slots);
+        let depth = self.call_stack.len();
+        self.call_stack.push(Call {
+            slots,
+            env: closure.env.clone(),
+            globals: Some(closure.globals.clone()),
+        });
+        if let Some(stats) = &mut self.stats {
+            stats.max_depth = stats.max_depth.max(depth);
+        }
+        if let Some(profile) = &mut self.profile {
+            profile.enter(&name);
+        }
+        // Self calls in tail position reuse this frame instead of growing the stack.
+        let result = loop {
+            if let Some(stats) = &mut self.stats {
+                stats.calls += 1;
+            }
+            match self.eval_tail(&name, &closure, &closure.function.value) {
+                Ok(Tail::Value(x)) => break Ok(x),
+                Ok(Tail::Call(slots)) => {
+                    if let Some(loops) = &mut self.loops {
+                        match loops.enter(&name, &closure, &slots, &self.call_stack) {
+                            Ok(x) => loop_keys.extend(x),
+                            Err(e) => break Err(e),
+                        }
+                    }
+                    // A tail call shows up at the depth of the frame it reuses.
+                    self.trace_call(&indent, &name, &closure, &slots);
+                    self.call_stack.replace_slots(slots)
+                },
+                Err(e) => break Err(e),
+            }
+        };
+        self.call_stack.pop();
+        if let Some(profile) = &mut self.profile {
+            profile.leave();
+        }
+        if result.is_err() {
+            self.backtrace.push(BacktraceFrame { name: name.clone(), location: callee.location.clone() });
+        }
+        if let Some(loops) = &mut self.loops {
+            loops.leave(loop_keys);
+        }
+        if let (true, Ok(x)) = (self.config.trace, &result) {
+            eprintln!("{indent}{name} = {x}");
+        }
+        if let (Some(memo), Some(key), Ok(x)) = (&mut self.memo, memo_key, &result) {
+            memo.results.insert(key, x.clone());
+        }
+        result
+    }
+
+    /// Prints a value, which is also what the `print` evaluates to.
+    fn print_value(&mut self, x: &ast::Print) -> EvalResult {
+        let value = self.eval_term(&x.value)?;
+        write!(self.output, "{value}")?;
+        Ok(value)
+    }
+
+    /// Creates the closure for a function term, named after the `let` binding it
+    /// when there is one.
+    fn function_value(&self, function: &ast::Function, name: Option<&String>) -> RuntimeValue {
+        let env = self.call_stack.capture();
+        let globals = self.call_stack.capture_globals();
+        RuntimeValue::Function(Rc::new(Closure { function: function.clone(), env, globals, name: name.cloned() }))
+    }
+
+    /// Evaluates the value of a `let` and binds it in the current frame.
+    fn bind_let(&mut self, name: &parser::Var, value: &ast::Term) -> Result<(), InterpreterError> {
+        let value = match value {
+            ast::Term::Function(x) => self.function_value(x, Some(&name.text)),
+            value => self.eval_term(value)?,
+        };
+        self.call_stack.set_var(name, value);
+        Ok(())
+    }
+
+    /// Binds a chain of `let`s and returns the term after the last one. The
+    /// chain is walked in a loop, since generated programs can have enough
+    /// bindings to overflow the native stack with one frame each.
+    fn bind_lets<'a>(&mut self, mut x: &'a ast::Let) -> Result<&'a ast::Term, InterpreterError> {
+        loop {
+            self.bind_let(&x.name, &x.value)?;
+            match &*x.next {
+                ast::Term::Let(y) => {
+                    if let Some(stats) = &mut self.stats {
+                        stats.evals += 1;
+                    }
+                    x = y
+                },
+                next => return Ok(next),
+            }
+        }
+    }
+
+    /// Resolves and evaluates a top-level term.
+    pub fn eval(&mut self, expr: &ast::Term) -> EvalResult {
+        resolver::resolve(expr, &mut self.call_stack.interner);
+        self.backtrace.clear();
+        self.eval_term(expr)
+    }
+
+    fn eval_term(&mut self, expr: &ast::Term) -> EvalResult {
+        if let Some(stats) = &mut self.stats {
+            stats.evals += 1;
+        }
+        self.burn_fuel()?;
+        Ok(match expr {
+            ast::Term::Error(x) => return Err(InterpreterError::ParseError {
+                message: x.message.to_string(),
+                location: x.location.clone(),
+                expected: vec![],
+            }),
+            ast::Term::Int(x) => RuntimeValue::Int(x.value),
+            ast::Term::Float(x) => RuntimeValue::Float(x.value),
+            ast::Term::Str(x) => RuntimeValue::Str(x.value.to_string()),
+            ast::Term::Call(x) => self.call_fn(&x.callee, &x.arguments)?,
+            ast::Term::Binary(x) => match x.op {
+                // Logical operators only evaluate the right side when it decides the result.
+                ast::BinaryOp::And =>
+                    RuntimeValue::Bool(as_logical(&self.eval_term(&x.lhs)?)? && as_logical(&self.eval_term(&x.rhs)?)?),
+                ast::BinaryOp::Or =>
+                    RuntimeValue::Bool(as_logical(&self.eval_term(&x.lhs)?)? || as_logical(&self.eval_term(&x.rhs)?)?),
+                _ => {
+                    let lhs = self.eval_term(&x.lhs)?;
+                    let rhs = self.eval_term(&x.rhs)?;
+                    eval_binary_op(x.op, lhs, rhs)?
+                },
+            },
+            ast::Term::Function(x) => self.function_value(x, None),
+            ast::Term::Let(x) => {
+                let next = self.bind_lets(x)?;
+                self.eval_term(next)?
+            },
+            ast::Term::If(x) => {
+                let condition = as_condition(self.eval_term(&x.condition)?, self.config.lenient)?;
+                if condition { self.eval_term(&x.then)? }
+                else {
+                    match &x.otherwise {
+                        Some(otherwise) => self.eval_term(otherwise)?,
+                        None => RuntimeValue::Void(()),
+                    }
+                }
+            },
+            ast::Term::Print(x) => self.print_value(x)?,
+            ast::Term::First(x) => {
+                match self.eval_term(&x.value)?.as_tuple() {
+                    Ok([first, _]) => first.clone(),
+                    _ => return Err(InterpreterError::type_mismatch_at("first applied to a value that is not a pair", &x.location)),
+                }
+            },
+            ast::Term::Second(x) => {
+                match self.eval_term(&x.value)?.as_tuple() {
+                    Ok([_, second]) => second.clone(),
+                    _ => return Err(InterpreterError::type_mismatch_at("second applied to a value that is not a pair", &x.location)),
+                }
+            },
+            ast::Term::Bool(x) => RuntimeValue::Bool(x.value),
+            ast::Term::Tuple(x) =>
+                RuntimeValue::Tuple(x.elements().map(|y| self.eval_term(y)).collect::<Result<_, _>>()?),
+            ast::Term::Var(x) => {
+                let value = self.call_stack.get_var(x)?;
+                self.force(value)?
+            },
+        })
+    }
+}
+
+/// Evaluates a top-level term with a default [`Interpreter`], printing to
+/// stdout.
+pub fn eval(expr: &ast::Term) -> EvalResult {
+    let mut interpreter = Interpreter::new(Config::default());
+    interpreter.call_stack.push(global_frame());
+    let result = interpreter.eval(expr);
+    interpreter.output.flush()?;
+    result
+}
+
+/// Parses and evaluates source code with a default [`Interpreter`],
+/// printing to stdout. Parse errors are reported at the first problem found.
+///
+/// ```
+/// use caramuru::RuntimeValue;
+///
+/// let value = caramuru::eval_str("let x = 1; x + 2").unwrap();
+/// assert_eq!(value, RuntimeValue::Int(3));
+/// ```
+pub fn eval_str(source: &str) -> EvalResult {
+    let file = parser::parse_or_report("<string>", source)?;
+    eval(&file.expression)
+}
+
+/// A writer into a buffer that stays readable after the writer is handed
+/// over to an [`Interpreter`].
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<std::cell::RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Evaluates a program given as a JSON AST and returns what it printed.
+/// Nothing touches the file system or stdout, so this is the entry point
+/// for hosts like WASM.
+pub fn run_json(ast_json: &str) -> Result<String, InterpreterError> {
+    let file: ast::File = serde_json::from_str(ast_json)?;
+    let output = SharedBuffer::default();
+    let mut interpreter = Interpreter::with_output(Config::default(), Box::new(output.clone()));
+    interpreter.call_stack.push(global_frame());
+    interpreter.eval(&file.expression)?;
+    let text = String::from_utf8_lossy(&output.0.borrow()).into_owned();
+    Ok(text)
+}
+
+/// The bottom frame of the call stack, where top-level code runs.
+pub fn global_frame() -> Call {
+    Call {
+        slots: vec![],
+        env: None,
+        globals: None,
+    }
+}
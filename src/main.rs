@@ -1,219 +1,447 @@
-use std::{io::Read, collections::HashMap, cell::RefCell, rc::Rc};
+use std::io::{IsTerminal, Read, Write};
 
-use lalrpop_util::lalrpop_mod;
+use caramuru::{
+    ast, global_frame, gzip, optimize, parser, printer, validate, BacktraceFrame, Config, Interpreter, InterpreterError, RuntimeValue,
+};
 
-pub mod ast;
-pub mod parser;
+type VoidResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+/// The code to exit with, or an error that ends the program.
+type ExitResult = Result<i32, Box<dyn std::error::Error + Send + Sync>>;
 
-lalrpop_mod! {
-    #[allow(warnings)]
-    pub rinha
-}
+const USAGE: &str = "usage: rinha-compiler [options] <ast-json-path | source file | directory | ->
+  pass - to read a JSON AST from stdin, .json.gz files are decompressed
+  pass a directory to run every program in it, each after a header naming it,
+  and summarize which failed
+  --repl             evaluate expressions read line by line from stdin
+  --check            only parse and validate, printing one error per line
+  --ast              print the syntax tree instead of evaluating it
+  --emit-json        print the syntax tree as a JSON AST instead of evaluating it
+  --graphviz         print the syntax tree as a Graphviz DOT graph instead of evaluating it
+  -O                 rewrite recursion through + and * on integers into loops before running
+  --max-depth <n>    maximum call depth, 10000 by default
+  --fuel <n>         fail after evaluating n terms, or running n instructions with --vm
+  --arg <name=value> bind a global to an int, a bool or else a string before the program runs
+  --memoize          cache the results of pure recursive functions
+  --trace            log every function call and its result to stderr
+  --stats            print execution statistics to stderr
+  --profile          print the time spent in each function to stderr
+  --dump-scope       print the top-level bindings to stderr once the program has run
+  --lazy             pass arguments by need, evaluating each the first time it's read
+  --lenient          accept integers as if conditions, nonzero ones being true
+  --no-env           leave out get_env, so programs can't read environment variables
+  --allow-io         provide read_line, so programs can read lines from stdin
+  --detect-loops     fail when a call keeps calling itself with the same arguments
+  --json-errors      print a failure as a JSON object with its kind, message, location and backtrace
+  --color=<when>     color error output: auto (when stderr is a terminal), always or never
+  --no-color         the same as --color=never
+  --time             print how long the evaluation took to stderr
+  --vm               run on the bytecode machine, which ignores --memoize, --trace, --stats,
+                     --profile, --detect-loops and --lazy";
 
-pub struct Call {
-    pub callee: Option<ast::Term>,
-    pub arguments: Vec<ast::Term>,
-    pub location: ast::Location,
-    pub var_scope: HashMap<String, RuntimeValue>
+/// When error output is colored.
+#[derive(Default, Clone, Copy)]
+enum Color {
+    /// When stderr is a terminal and `NO_COLOR` isn't set.
+    #[default]
+    Auto,
+    Always,
+    Never,
 }
 
-type VoidResult = Result<(), Box<dyn std::error::Error>>;
-
-#[derive(Clone)]
-pub struct CallStack {
-    inner: Rc<RefCell<Vec<Call>>>
+/// ANSI escapes for error output, all empty when color is off.
+struct Style {
+    error: &'static str,
+    location: &'static str,
+    reset: &'static str,
 }
 
-impl CallStack {
-    pub fn new() -> Self {
-        Self {
-            inner: Rc::new(RefCell::new(Vec::new())),
-        }
-    }
-    pub fn push(&self, value: Call) {
-        self.inner.borrow_mut().push(value);
-    }
-    pub fn len(&self) -> usize {
-        self.inner.borrow().len()
-    }
-    pub fn pop(&self) {
-        self.inner.borrow_mut().pop();
-    }
-    pub fn get_var(&self, name: &String) -> RuntimeValue {
-        let len = self.len();
-        let stack = &self.inner.borrow();
-        for i in (0..len).rev() {
-            let var_scope = &stack[i].var_scope;
-            for (varname, runtime_value) in var_scope {
-                if name.ne(varname) { continue }
-                return match runtime_value {
-                    RuntimeValue::Int(x) => RuntimeValue::Int(*x),
-                    RuntimeValue::Str(x) => RuntimeValue::Str(x.to_string()),
-                    RuntimeValue::Bool(x) => RuntimeValue::Bool(*x),
-                    RuntimeValue::Tuple(_) => todo!(),
-                    RuntimeValue::Function(x) => RuntimeValue::Function(x.clone()),
-                    RuntimeValue::Void(_) => RuntimeValue::Void(()),
-                }
-            }
+impl Style {
+    fn new(enabled: bool) -> Self {
+        match enabled {
+            true => Self { error: "\x1b[1;31m", location: "\x1b[34m", reset: "\x1b[0m" },
+            false => Self { error: "", location: "", reset: "" },
         }
-        panic!("reference \"{name}\" not found")
-    }
-    pub fn set_var(&self, name: &String, value: RuntimeValue) {
-        let last = self.len() - 1;
-        let var_scope = &mut self.inner.borrow_mut()[last].var_scope;
-        var_scope.insert(name.to_string(), value);
     }
 }
 
-pub enum RuntimeValue {
-    Int(i32),
-    Str(String),
-    Bool(bool),
-    Tuple(Vec<RuntimeValue>),
-    Function(ast::Function),
-    Void(())
+/// Command line options.
+#[derive(Default)]
+struct Options {
+    input_path: Option<String>,
+    repl: bool,
+    check: bool,
+    ast: bool,
+    emit_json: bool,
+    graphviz: bool,
+    time: bool,
+    dump_scope: bool,
+    json_errors: bool,
+    color: Color,
+    vm: bool,
+    optimize: bool,
+    /// Names and values given with `--arg`.
+    args: Vec<(String, String)>,
+    config: Config,
 }
 
-fn call_fn(callee: ast::Term, arguments: Vec<ast::Term>, call_stack: &CallStack) -> RuntimeValue {
-    let name = match callee {
-        ast::Term::Var(x) => x.text,
-        _ => panic!("callee is not a var")
-    };
-    let result = match call_stack.get_var(&name) {
-        RuntimeValue::Function(x) => {
-            match x {
-                ast::Function { parameters, value, location } => {
-                    if arguments.len() != parameters.len() {
-                        panic!("wrong number of args passed to {name}");
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--repl" => options.repl = true,
+                "--check" => options.check = true,
+                "--ast" => options.ast = true,
+                "--emit-json" => options.emit_json = true,
+                "--graphviz" => options.graphviz = true,
+                "--time" => options.time = true,
+                "--dump-scope" => options.dump_scope = true,
+                "--json-errors" => options.json_errors = true,
+                "--no-color" => options.color = Color::Never,
+                "--color=auto" => options.color = Color::Auto,
+                "--color=always" => options.color = Color::Always,
+                "--color=never" => options.color = Color::Never,
+                x if x.starts_with("--color=") => return Err(format!("invalid {x}, expected auto, always or never")),
+                "--vm" => options.vm = true,
+                "-O" => options.optimize = true,
+                "--memoize" => options.config.memoize = true,
+                "--trace" => options.config.trace = true,
+                "--stats" => options.config.stats = true,
+                "--profile" => options.config.profile = true,
+                "--detect-loops" => options.config.detect_loops = true,
+                "--no-env" => options.config.env = false,
+                "--allow-io" => options.config.io = true,
+                "--lenient" => options.config.lenient = true,
+                "--lazy" => options.config.lazy = true,
+                "--arg" => {
+                    let value = args.next().ok_or("--arg expects name=value")?;
+                    match value.split_once('=') {
+                        Some((name, value)) if !name.is_empty() => options.args.push((name.to_string(), value.to_string())),
+                        _ => return Err(format!("invalid --arg \"{value}\", expected name=value")),
                     }
-                    let mut var_scope = HashMap::new();
-                    let mut i = 0;
-                    for arg in &arguments {
-                        let key = &parameters[i].text;
-                        i = i + 1;
-                        let val = eval(arg.clone(), &call_stack);
-                        var_scope.insert(key.to_string(), val);
-                    }
-                    call_stack.push(Call {
-                        arguments,
-                        callee: Some(ast::Term::Str(ast::Str { ..Default::default() })),
-                        location: location.clone(),
-                        var_scope
-                    });
-                    eval(*value.clone(), &call_stack)
-                }
+                },
+                "--fuel" => {
+                    let value = args.next().ok_or("--fuel expects a number")?;
+                    options.config.fuel = Some(value.parse().map_err(|_| format!("invalid --fuel \"{value}\""))?);
+                },
+                "--max-depth" => {
+                    let value = args.next().ok_or("--max-depth expects a number")?;
+                    options.config.max_depth = value.parse().map_err(|_| format!("invalid --max-depth \"{value}\""))?;
+                },
+                x if x.starts_with("--") => return Err(format!("unknown option {x}")),
+                _ => options.input_path = Some(arg),
             }
         }
-        _ => panic!("error: \"{name}\" is not a function"),
-    };
-    call_stack.pop();
-    result
+        Ok(options)
+    }
 }
 
-fn eval_binary_op(op: ast::BinaryOp, l: RuntimeValue, r: RuntimeValue) -> i32 {
-    let l = match l {
-        RuntimeValue::Int(x) => x,
-        _ => panic!("operand is not an integer"),
-    };
-    let r = match r {
-        RuntimeValue::Int(x) => x,
-        _ => panic!("operand is not an integer"),
-    };
-    match op {
-        ast::BinaryOp::Add => l + r,
-        ast::BinaryOp::Sub => l - r,
-        ast::BinaryOp::Mul => l * r,
-        ast::BinaryOp::Div => l / r,
-        ast::BinaryOp::Rem => l % r,
-        ast::BinaryOp::Eq => if l == r {1} else {0},
-        ast::BinaryOp::Neq => if l != r {1} else {0},
-        ast::BinaryOp::Lt => if l < r {1} else {0},
-        ast::BinaryOp::Gt => if l > r {1} else {0},
-        ast::BinaryOp::Lte => if l <= r {1} else {0},
-        ast::BinaryOp::Gte => if l >= r {1} else {0},
-        ast::BinaryOp::And => if l != 0 && r != 0 {1} else {0},
-        ast::BinaryOp::Or => if l != 0 || r != 0 {1} else {0},
+/// The value of an `--arg`. Values that read as an int or a bool are one,
+/// the others are strings.
+fn arg_value(value: &str) -> RuntimeValue {
+    match value {
+        "true" => RuntimeValue::Bool(true),
+        "false" => RuntimeValue::Bool(false),
+        _ => match value.parse() {
+            Ok(x) => RuntimeValue::Int(x),
+            Err(_) => RuntimeValue::Str(value.to_string()),
+        },
     }
 }
 
-fn print_value(x: ast::Print, call_stack: &CallStack) -> RuntimeValue {
-    match eval(*x.value, call_stack) {
-        RuntimeValue::Int(x) => { print!("{x}") },
-        RuntimeValue::Str(x) => { print!("{x}") },
-        RuntimeValue::Bool(x) => { print!("{x}") },
-        RuntimeValue::Tuple(_) => { print!("[tuple]") },
-        RuntimeValue::Function(_) => { print!("[function]") },
-        RuntimeValue::Void(_) => { print!("[void]") },
-    };
-    RuntimeValue::Void(())
+impl Options {
+    fn color_enabled(&self) -> bool {
+        match self.color {
+            Color::Auto => std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+
+    /// Creates an interpreter configured by these options, with the global
+    /// frame already pushed and the `--arg` globals bound.
+    fn interpreter(&self) -> Interpreter {
+        let mut interpreter = Interpreter::new(self.config.clone());
+        interpreter.call_stack.push(global_frame());
+        for (name, value) in &self.args {
+            interpreter.call_stack.set_global(name, arg_value(value));
+        }
+        interpreter
+    }
 }
 
-fn eval(expr: ast::Term, call_stack: &CallStack) -> RuntimeValue {
-    match expr {
-        ast::Term::Error(x) => panic!("Panicked at {}:{} - {}", x.location.filename, x.location.start, x.full_text),
-        ast::Term::Int(x) => RuntimeValue::Int(x.value),
-        ast::Term::Str(x) => RuntimeValue::Str(x.value),
-        ast::Term::Call(x) => call_fn(*x.callee, x.arguments, call_stack),
-        ast::Term::Binary(x) =>
-            RuntimeValue::Int(
-                eval_binary_op(x.op, eval(*x.lhs, call_stack), eval(*x.rhs, call_stack))
-            ),
-        ast::Term::Function(x) => RuntimeValue::Function(x),
-        ast::Term::Let(x) => {
-            call_stack.set_var(&x.name.text, eval(*x.value, &call_stack));
-            eval(*x.next, &call_stack)
-        },
-        ast::Term::If(x) => {
-            match eval(*x.condition, &call_stack) {
-                RuntimeValue::Bool(y) =>
-                    if y { eval(*x.then, &call_stack) }
-                    else { eval(*x.otherwise, &call_stack) },
-                RuntimeValue::Int(y) =>
-                    if y != 0 { eval(*x.then, &call_stack) }
-                    else { eval(*x.otherwise, &call_stack) },
-                _ => panic!("error: condition is not a boolean"),
-            }
+/// Points at where an error is, quoting the line of source with the part of
+/// it that's wrong underlined. JSON ASTs have no source to quote.
+fn print_location(location: &ast::Location, style: &Style) {
+    eprintln!("  {}--> {location}{}", style.location, style.reset);
+    if let Some(source) = location.source() {
+        let snippet = location.snippet(&source);
+        let (text, carets) = snippet.split_once('\n').unwrap_or((&snippet, ""));
+        eprintln!("   {}|{} {text}", style.location, style.reset);
+        eprintln!("   {}|{} {}{carets}{}", style.location, style.reset, style.error, style.reset);
+    }
+}
+
+/// Prints an error as a single line of JSON to stderr, for tools to read.
+fn print_json_error(error: &InterpreterError, backtrace: &[BacktraceFrame]) {
+    let backtrace: Vec<&str> = backtrace.iter().map(|x| x.name.as_str()).collect();
+    let error = serde_json::json!({
+        "kind": error.kind(),
+        "message": error.to_string(),
+        "location": error.location(),
+        "expected": match error {
+            InterpreterError::ParseError { expected, .. } => &expected[..],
+            _ => &[],
         },
-        ast::Term::Print(x) => print_value(x, &call_stack),
-        ast::Term::First(_) => todo!(),
-        ast::Term::Second(_) => todo!(),
-        ast::Term::Bool(x) => RuntimeValue::Bool(x.value),
-        ast::Term::Tuple(_) => todo!(),
-        ast::Term::Var(x) => call_stack.get_var(&x.text)
+        "backtrace": backtrace,
+    });
+    eprintln!("{error}");
+}
+
+/// Reads one expression per line and prints its value. Bindings made by
+/// `let` stay in the global frame, so they are visible to the next lines.
+fn repl(options: &Options, style: &Style) -> VoidResult {
+    let mut interpreter = options.interpreter();
+    let stdin = std::io::stdin();
+    let mut line = String::new();
+    loop {
+        write!(interpreter.output, "> ")?;
+        interpreter.output.flush()?;
+        line.clear();
+        if stdin.read_line(&mut line)? == 0 {
+            return Ok(())
+        }
+        if line.trim().is_empty() { continue }
+        match parser::parse_or_report("repl", &line) {
+            Ok(f) => {
+                match interpreter.eval(&f.expression) {
+                    Ok(x) => writeln!(interpreter.output, "{x}")?,
+                    Err(e) => {
+                        writeln!(interpreter.output)?;
+                        interpreter.output.flush()?;
+                        eprintln!("{}error{}: {e}", style.error, style.reset)
+                    },
+                }
+            },
+            Err(e) => eprintln!("{:?}", miette::Report::new(e)),
+        }
     }
 }
 
+/// Native stack size of the interpreter thread. Every rinha call nests a few
+/// `eval` frames, so [`caramuru::DEFAULT_MAX_DEPTH`] calls need far more than the main
+/// thread gets.
+const STACK_SIZE: usize = 1 << 30;
+
 fn main() -> VoidResult {
-    let args: Vec<String> = std::env::args().collect();
-    let input_path = match args.get(1) {
-        None => { eprintln!("usage: rinha-compiler <ast-json-path | source file>"); return Ok(()) },
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)?
+        .join()
+        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+}
+
+fn run() -> VoidResult {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("error: {e}");
+            eprintln!("{USAGE}");
+            std::process::exit(2)
+        }
+    };
+    let color = options.color_enabled();
+    miette::set_hook(Box::new(move |_| Box::new(miette::MietteHandlerOpts::new().color(color).build())))?;
+    let style = Style::new(color);
+    if options.repl {
+        return repl(&options, &style);
+    }
+    let input_path = match &options.input_path {
+        None => { eprintln!("{USAGE}"); return Ok(()) },
         Some(x) => x
     };
-    let mut input_bytes = std::fs::File::open(input_path)?;
+    let code = match std::path::Path::new(input_path).is_dir() {
+        true => run_dir(&options, &style, input_path)?,
+        false => run_file(&options, &style, input_path)?,
+    };
+    if code != 0 {
+        std::process::exit(code)
+    }
+    Ok(())
+}
+
+/// Runs every program in a directory, in the order of their names, and
+/// prints which ones failed. Fails if any of them did. The output of each
+/// program follows a header naming it, like `head` does with many files.
+fn run_dir(options: &Options, style: &Style, path: &str) -> ExitResult {
+    let mut paths: Vec<_> = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|x| x.path().to_string_lossy().into_owned()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|x| [".rinha", ".json", ".json.gz"].iter().any(|y| x.ends_with(y)));
+    paths.sort();
+    let mut failed = vec![];
+    for (i, path) in paths.iter().enumerate() {
+        // Programs don't end what they print with a newline, so the header
+        // starts a line of its own.
+        let separator = if i == 0 { "" } else { "\n" };
+        println!("{separator}==> {path} <==");
+        let code = match run_file(options, style, path) {
+            Ok(x) => x,
+            Err(e) => {
+                eprintln!("{}error{}: {path}: {e}", style.error, style.reset);
+                1
+            },
+        };
+        if code != 0 {
+            failed.push(path);
+        }
+    }
+    if !paths.is_empty() {
+        println!();
+    }
+    eprintln!();
+    for path in &paths {
+        let status = if failed.contains(&path) { "FAILED" } else { "ok" };
+        eprintln!("{status:>6} {path}");
+    }
+    eprintln!("{} programs, {} passed, {} failed", paths.len(), paths.len() - failed.len(), failed.len());
+    Ok(if failed.is_empty() { 0 } else { 1 })
+}
+
+/// Runs a single program, returning the code the process should exit with.
+fn run_file(options: &Options, style: &Style, input_path: &str) -> ExitResult {
     let mut buf = vec![];
-    input_bytes.read_to_end(&mut buf)?;
-    let ast = if input_path.ends_with(".json") {
-        serde_json::from_slice::<ast::File>(&buf)?
+    if input_path == "-" {
+        std::io::stdin().read_to_end(&mut buf)?;
+    } else {
+        let mut input_bytes = match std::fs::File::open(input_path) {
+            Ok(x) => x,
+            Err(e) => {
+                eprintln!("error: could not open \"{input_path}\": {e}");
+                eprintln!("{USAGE}");
+                return Ok(2)
+            }
+        };
+        input_bytes.read_to_end(&mut buf)?;
+    }
+    if input_path.ends_with(".json.gz") {
+        buf = gzip::decompress(&buf)?;
+    }
+    // Parsed source names its locations after the path it was read from, so
+    // errors point at the file that was run. A JSON AST keeps the filenames
+    // it was compiled with.
+    let mut ast = if input_path == "-" || input_path.ends_with(".json") || input_path.ends_with(".json.gz") {
+        // Generated ASTs can nest far deeper than serde_json's default limit,
+        // and the interpreter thread has the stack for them.
+        let mut deserializer = serde_json::Deserializer::from_slice(&buf);
+        deserializer.disable_recursion_limit();
+        <ast::File as serde::Deserialize>::deserialize(&mut deserializer)?
     }
     else {
-        let mut errors = vec![];
         let input = std::str::from_utf8(&buf)?;
-        match parser::FileParser::new().parse(&mut errors, &input_path, input) {
+        match parser::parse_or_report(input_path, input) {
             Ok(f) => f,
+            Err(e) if options.check => {
+                for error in e.errors() {
+                    let span = error.span();
+                    let location = ast::Location::new(span.offset(), span.offset() + span.len(), input_path);
+                    match error.expected() {
+                        [] => eprintln!("{location}: {error}"),
+                        expected => eprintln!("{location}: {error}, expected one of {}", expected.join(", ")),
+                    }
+                }
+                return Ok(1)
+            },
+            Err(e) if options.json_errors => {
+                print_json_error(&InterpreterError::from(e), &[]);
+                return Ok(1)
+            },
             Err(e) => {
-                eprintln!("parse error: {}", e);
-                std::process::exit(1)
+                eprintln!("{:?}", miette::Report::new(e));
+                return Ok(1)
             }
         }
     };
-    let call_stack: CallStack = CallStack::new();
-    call_stack.push(Call {
-        arguments: vec![],
-        callee: None,
-        location: ast::Location { start: 1, end: 1, filename: ast.name },
-        var_scope: HashMap::new()
-    });
-    eval(ast.expression, &call_stack);
+    if options.check {
+        let problems = validate::validate(&ast.expression);
+        for problem in &problems {
+            eprintln!("{}: {}", problem.location, problem.message);
+        }
+        return Ok(if problems.is_empty() { 0 } else { 1 })
+    }
+    // Before --ast, --emit-json and --graphviz, so that they show what -O rewrote.
+    if options.optimize {
+        optimize::optimize(&mut ast.expression);
+    }
+    if options.ast {
+        print!("{}", printer::render(&ast.expression));
+        return Ok(0)
+    }
+    if options.graphviz {
+        print!("{}", printer::render_dot(&ast.expression));
+        return Ok(0)
+    }
+    if options.emit_json {
+        let mut output = std::io::stdout().lock();
+        serde_json::to_writer(&mut output, &ast)?;
+        writeln!(output)?;
+        return Ok(0)
+    }
+    let problems = validate::validate(&ast.expression);
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("{}error{}: {}", style.error, style.reset, problem.message);
+            print_location(&problem.location, style);
+        }
+        return Ok(1)
+    }
+    let mut interpreter = options.interpreter();
+    let start = std::time::Instant::now();
+    let result = match options.vm {
+        true => interpreter.eval_vm(&ast.expression),
+        false => interpreter.eval(&ast.expression),
+    };
+    interpreter.output.flush()?;
+    if options.time {
+        eprintln!("time: {:?}", start.elapsed());
+    }
+    if let Some(stats) = &interpreter.stats {
+        eprintln!("{stats}");
+    }
+    if let Some(profile) = &interpreter.profile {
+        eprintln!("{profile}");
+    }
+    if options.dump_scope {
+        for (name, value) in interpreter.call_stack.globals() {
+            eprintln!("{name} = {value}");
+        }
+    }
+    if let Err(e) = result {
+        if options.json_errors {
+            print_json_error(&e, &interpreter.backtrace);
+            return Ok(1)
+        }
+        eprintln!("{}error{}: {e}", style.error, style.reset);
+        // Without the source, like with a JSON AST, this is a byte offset.
+        if let Some(location) = e.location() {
+            print_location(location, style);
+        }
+        // Deep recursion repeats the same call thousands of times, so runs
+        // of it are folded into one line.
+        let backtrace = &interpreter.backtrace;
+        let mut i = 0;
+        while let Some(frame) = backtrace.get(i) {
+            let repeats = backtrace[i..].iter()
+                .take_while(|x| x.name == frame.name && x.location == frame.location)
+                .count();
+            eprintln!("  at {} (called at {}{}{})", frame.name, style.location, frame.location, style.reset);
+            if repeats > 1 {
+                eprintln!("  ... {} more times", repeats - 1);
+            }
+            i += repeats;
+        }
+        return Ok(1)
+    }
 
-    Ok(())
+    Ok(0)
 }
@@ -0,0 +1,257 @@
+//! Rewrites recursive functions into tail-recursive ones, which both the
+//! interpreter and the VM run as loops, for `-O`.
+//!
+//! The only pattern rewritten is linear recursion through `+` or `*`:
+//!
+//! ```text
+//! let sum = fn (n) => { if (n == 1) { n } else { n + sum(n - 1) } };
+//! ```
+//!
+//! becomes a function that passes the partial result along instead of
+//! waiting on the recursive call:
+//!
+//! ```text
+//! let sum = fn (n) => {
+//!   let sum'step = fn (n, sum'acc) => {
+//!     if (n == 1) { sum'acc + n } else { sum'step(n - 1, sum'acc + n) }
+//!   };
+//!   if (n == 1) { n } else { sum'step(n - 1, n) }
+//! };
+//! ```
+//!
+//! That regroups the operations, which only gives the same result when
+//! they're on integers, so the operands have to be integer arithmetic over
+//! literals and parameters that are integers on every call. A parameter is
+//! one when each call that follows the function passes it integer
+//! arithmetic over literals, and the recursive call passes it integer
+//! arithmetic over literals and such parameters. An overflow the original
+//! order would run into may still go unreported when the final result fits.
+
+use std::rc::Rc;
+
+use crate::{
+    ast::{self, BinaryOp, Term},
+    parser::Var,
+};
+
+/// Rewrites every function in `term` that the pass knows how to.
+pub fn optimize(term: &mut Term) {
+    match term {
+        Term::Error(_) | Term::Int(_) | Term::Float(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_) => {},
+        Term::Let(_) => {
+            // Chains of lets are walked in a loop rather than recursing once
+            // per binding, like in the resolver.
+            let mut term = term;
+            while let Term::Let(x) = term {
+                if let Term::Function(function) = &mut *x.value {
+                    optimize_function(function);
+                    if let Some(y) = accumulate(&x.name.text, function, &x.next) {
+                        *function = y;
+                    }
+                } else {
+                    optimize(&mut x.value);
+                }
+                term = &mut x.next;
+            }
+            optimize(term);
+        },
+        Term::Function(x) => optimize_function(x),
+        Term::Call(x) => {
+            optimize(&mut x.callee);
+            x.arguments.iter_mut().for_each(optimize);
+        },
+        Term::Binary(x) => {
+            optimize(&mut x.lhs);
+            optimize(&mut x.rhs);
+        },
+        Term::If(x) => {
+            optimize(&mut x.condition);
+            optimize(&mut x.then);
+            if let Some(otherwise) = &mut x.otherwise {
+                optimize(otherwise);
+            }
+        },
+        Term::Print(x) => optimize(&mut x.value),
+        Term::First(x) => optimize(&mut x.value),
+        Term::Second(x) => optimize(&mut x.value),
+        Term::Tuple(x) => {
+            optimize(&mut x.first);
+            optimize(&mut x.second);
+            x.rest.iter_mut().for_each(optimize);
+        },
+    }
+}
+
+fn optimize_function(function: &mut ast::Function) {
+    optimize(Rc::make_mut(&mut function.value));
+}
+
+/// The tail-recursive form of a function bound to `name` and called in
+/// `next`, if its body is `if (condition) { base } else { operand op
+/// name(arguments) }`, with the operands either way around.
+fn accumulate(name: &str, function: &ast::Function, next: &Term) -> Option<ast::Function> {
+    let Term::If(body) = &*function.value else { return None };
+    let Some(Term::Binary(recursion)) = body.otherwise.as_deref() else { return None };
+    if !matches!(recursion.op, BinaryOp::Add | BinaryOp::Mul) {
+        return None
+    }
+    let (operand, call) = match (&*recursion.lhs, &*recursion.rhs) {
+        (operand, Term::Call(call)) | (Term::Call(call), operand) if is_self_call(name, call) => (operand, call),
+        _ => return None,
+    };
+    let step = format!("{name}'step");
+    let acc = format!("{name}'acc");
+    let parameters = &function.parameters;
+    let names = [name, &step, &acc];
+    let clean = |x: &Term| !mentions(x, &names);
+    if call.arguments.len() != parameters.len()
+        || parameters.iter().any(|x| names.contains(&x.text.as_str()))
+        || !clean(&body.condition)
+        || !clean(&body.then)
+        || !clean(operand)
+        || !call.arguments.iter().all(clean)
+    {
+        return None
+    }
+    let integers = integer_parameters(name, function, call, next)?;
+    let integer = |x: &Var| parameters.iter().zip(&integers).any(|(y, integer)| *integer && y.text == x.text);
+    if !is_integer(&body.then, &integer) || !is_integer(operand, &integer) {
+        return None
+    }
+    let location = &function.location;
+    let var = |text: &str| Var { text: text.to_string(), location: location.clone(), slot: Default::default(), symbol: Default::default() };
+    let binary = |lhs: Term, rhs: Term| Term::Binary(ast::Binary {
+        lhs: lhs.into(),
+        op: recursion.op,
+        rhs: rhs.into(),
+        location: recursion.location.clone(),
+    });
+    let call_step = |last: Term| {
+        let mut arguments = call.arguments.clone();
+        arguments.push(last);
+        Term::Call(ast::Call { callee: Term::Var(var(&step)).into(), arguments, location: call.location.clone() })
+    };
+    let branch = |then: Term, otherwise: Term| Term::If(ast::If {
+        condition: body.condition.clone(),
+        then: then.into(),
+        otherwise: Some(otherwise.into()),
+        location: body.location.clone(),
+    });
+    let mut step_parameters = parameters.clone();
+    step_parameters.push(var(&acc));
+    let step_function = ast::Function {
+        parameters: step_parameters,
+        value: Rc::new(branch(
+            binary(Term::Var(var(&acc)), (*body.then).clone()),
+            call_step(binary(Term::Var(var(&acc)), operand.clone())),
+        )),
+        location: location.clone(),
+        frame_size: Default::default(),
+    };
+    let value = Term::Let(ast::Let {
+        name: var(&step),
+        value: Term::Function(step_function).into(),
+        next: branch((*body.then).clone(), call_step(operand.clone())).into(),
+        location: location.clone(),
+    });
+    Some(ast::Function {
+        parameters: parameters.clone(),
+        value: Rc::new(value),
+        location: location.clone(),
+        frame_size: Default::default(),
+    })
+}
+
+fn is_self_call(name: &str, call: &ast::Call) -> bool {
+    matches!(&*call.callee, Term::Var(x) if x.text == name)
+}
+
+/// Whether a term is arithmetic over integer literals and variables
+/// `integer` holds for, which evaluates to an integer or fails.
+fn is_integer(term: &Term, integer: &impl Fn(&Var) -> bool) -> bool {
+    match term {
+        Term::Int(_) => true,
+        Term::Var(x) => integer(x),
+        Term::Binary(x) => {
+            matches!(x.op, BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul)
+                && is_integer(&x.lhs, integer)
+                && is_integer(&x.rhs, integer)
+        },
+        _ => false,
+    }
+}
+
+/// Which parameters of the function bound to `name` are integers on every
+/// call, given its recursive `call` and the term `next` it's in scope of.
+/// None when `next` uses the function other than by calling it, since it
+/// could then be called from anywhere.
+fn integer_parameters(name: &str, function: &ast::Function, call: &ast::Call, next: &Term) -> Option<Vec<bool>> {
+    let parameters = &function.parameters;
+    let mut calls = vec![];
+    if !calls_to(next, name, &mut calls) || calls.iter().any(|x| x.len() != parameters.len()) {
+        return None
+    }
+    let mut integers: Vec<bool> = (0..parameters.len())
+        .map(|i| calls.iter().all(|x| is_integer(&x[i], &|_| false)))
+        .collect();
+    // Dropping a parameter can drop the ones the recursive call computes
+    // from it, so this runs until nothing changes.
+    loop {
+        let integer = |x: &Var| parameters.iter().zip(&integers).any(|(y, integer)| *integer && y.text == x.text);
+        let next: Vec<bool> = integers.iter().zip(&call.arguments).map(|(x, y)| *x && is_integer(y, &integer)).collect();
+        if next == integers {
+            return Some(integers)
+        }
+        integers = next;
+    }
+}
+
+/// Collects the arguments of every call to `name` in a term, and whether
+/// those calls are the only uses of it.
+fn calls_to<'a>(term: &'a Term, name: &str, calls: &mut Vec<&'a [Term]>) -> bool {
+    match term {
+        Term::Error(_) | Term::Int(_) | Term::Float(_) | Term::Str(_) | Term::Bool(_) => true,
+        Term::Var(x) => x.text != name,
+        Term::Let(x) => calls_to(&x.value, name, calls) && calls_to(&x.next, name, calls),
+        Term::Function(x) => calls_to(&x.value, name, calls),
+        Term::Call(x) => {
+            let direct = matches!(&*x.callee, Term::Var(y) if y.text == name);
+            if direct {
+                calls.push(&x.arguments);
+            }
+            (direct || calls_to(&x.callee, name, calls)) && x.arguments.iter().all(|y| calls_to(y, name, calls))
+        },
+        Term::Binary(x) => calls_to(&x.lhs, name, calls) && calls_to(&x.rhs, name, calls),
+        Term::If(x) => {
+            calls_to(&x.condition, name, calls)
+                && calls_to(&x.then, name, calls)
+                && x.otherwise.as_ref().is_none_or(|y| calls_to(y, name, calls))
+        },
+        Term::Print(x) => calls_to(&x.value, name, calls),
+        Term::First(x) => calls_to(&x.value, name, calls),
+        Term::Second(x) => calls_to(&x.value, name, calls),
+        Term::Tuple(x) => x.elements().all(|y| calls_to(y, name, calls)),
+    }
+}
+
+/// Whether any of `names` is used or bound anywhere in a term.
+fn mentions(term: &Term, names: &[&str]) -> bool {
+    let named = |x: &Var| names.contains(&x.text.as_str());
+    match term {
+        Term::Error(_) | Term::Int(_) | Term::Float(_) | Term::Str(_) | Term::Bool(_) => false,
+        Term::Var(x) => named(x),
+        Term::Let(x) => named(&x.name) || mentions(&x.value, names) || mentions(&x.next, names),
+        Term::Function(x) => x.parameters.iter().any(named) || mentions(&x.value, names),
+        Term::Call(x) => mentions(&x.callee, names) || x.arguments.iter().any(|y| mentions(y, names)),
+        Term::Binary(x) => mentions(&x.lhs, names) || mentions(&x.rhs, names),
+        Term::If(x) => {
+            mentions(&x.condition, names)
+                || mentions(&x.then, names)
+                || x.otherwise.as_ref().is_some_and(|y| mentions(y, names))
+        },
+        Term::Print(x) => mentions(&x.value, names),
+        Term::First(x) => mentions(&x.value, names),
+        Term::Second(x) => mentions(&x.value, names),
+        Term::Tuple(x) => x.elements().any(|y| mentions(y, names)),
+    }
+}
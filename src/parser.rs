@@ -1,4 +1,6 @@
-use crate::ast::{Element, Location};
+use std::cell::Cell;
+
+use crate::{ast::{Element, Location}, interner::Symbol, resolver::Slot};
 
 use miette::{NamedSource, SourceSpan};
 
@@ -12,6 +14,27 @@ pub use crate::rinha::*;
 pub struct Var {
     pub text: String,
     pub location: Location,
+
+    /// Where the value lives at runtime, set by [`crate::resolver`].
+    #[serde(skip)]
+    pub slot: Cell<Slot>,
+
+    /// The interned name, set by [`crate::resolver`] for globals.
+    #[serde(skip)]
+    pub symbol: Cell<Symbol>,
+}
+
+impl Var {
+    /// Stands in for the name of a function called without one, like
+    /// `(fn (x) => { x })(1)`, in errors and backtraces.
+    pub fn anonymous(location: &Location) -> Self {
+        Self {
+            text: "<anonymous>".to_string(),
+            location: location.clone(),
+            slot: Default::default(),
+            symbol: Default::default(),
+        }
+    }
 }
 
 impl Element for Var {
@@ -20,6 +43,35 @@ impl Element for Var {
     }
 }
 
+/// Reads the digits of an integer literal in `radix`, failing with
+/// [`InnerError::IntegerOutOfRange`] on the literal from `start` to `end`
+/// when it doesn't fit.
+pub fn parse_int<T>(digits: &str, radix: u32, start: usize, end: usize) -> Result<i32, lalrpop_util::ParseError<usize, T, InnerError>> {
+    i32::from_str_radix(digits, radix).map_err(|_| lalrpop_util::ParseError::User {
+        error: InnerError::IntegerOutOfRange { err_span: (start, end - start).into() },
+    })
+}
+
+/// Replaces the escape sequences of a string literal, `\n`, `\t`, `\"` and
+/// `\\`, with the characters they stand for. The grammar only lets those
+/// through.
+pub fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        result.push(match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some(x) => x,
+                None => break,
+            },
+            x => x,
+        });
+    }
+    result
+}
+
 /// The error type for the parser. It's useful to debug the parser
 /// or report errors to the final end-to-end user.
 ///
@@ -106,7 +158,7 @@ pub enum InnerError {
     /// The token is not recognized by the parser. It's a
     /// token that the parser doesn't recognize, but it's
     /// a valid token that can be placed in the place.
-    #[error("unrecognized token")]
+    #[error("unrecognized token `{token}`")]
     #[diagnostic(code(zu::unrecognized_token), url(docsrs))]
     UnrecoginzedToken {
         /// The unrecognized token. It's the span of the token
@@ -115,6 +167,12 @@ pub enum InnerError {
         #[label = "here"]
         err_span: SourceSpan,
 
+        /// The text of the token.
+        token: String,
+
+        /// The tokens that could have been there instead.
+        expected: Vec<String>,
+
         /// The help messages pointing to the expected tokens.
         ///
         /// It's useful to know what the parser expected to
@@ -132,6 +190,9 @@ pub enum InnerError {
         #[label = "here"]
         err_span: SourceSpan,
 
+        /// The tokens that could have been there instead.
+        expected: Vec<String>,
+
         #[help]
         help: String,
     },
@@ -144,6 +205,70 @@ pub enum InnerError {
         err_span: SourceSpan,
         token: String,
     },
+
+    /// An integer literal that doesn't fit in 32 bits.
+    #[error("integer literal out of range")]
+    #[diagnostic(
+        code(zu::integer_out_of_range),
+        url(docsrs),
+        help("integers go from -2147483648 to 2147483647")
+    )]
+    IntegerOutOfRange {
+        #[label = "here"]
+        err_span: SourceSpan,
+    },
+}
+
+impl ParseError {
+    /// Every error found, in the order the parser ran into them.
+    pub fn errors(&self) -> &[InnerError] {
+        &self.related
+    }
+}
+
+impl InnerError {
+    /// The span of the source that the error points at.
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            Self::FunctionBodyMissing { err_span }
+            | Self::ExpectedStatement { err_span }
+            | Self::UnsupportedRecordIndex { err_span }
+            | Self::InvalidToken { err_span }
+            | Self::UnrecoginzedToken { err_span, .. }
+            | Self::ExpectedToken { err_span, .. }
+            | Self::ExtraToken { err_span, .. }
+            | Self::IntegerOutOfRange { err_span } => *err_span,
+        }
+    }
+
+    /// The tokens the parser would have accepted where the error is, if
+    /// it knows them.
+    pub fn expected(&self) -> &[String] {
+        match self {
+            Self::UnrecoginzedToken { expected, .. } | Self::ExpectedToken { expected, .. } => expected,
+            _ => &[],
+        }
+    }
+}
+
+/// Keeps the first error only, since runtime errors point at a single
+/// location.
+impl From<ParseError> for crate::InterpreterError {
+    fn from(error: ParseError) -> Self {
+        let filename = error.source_code.name();
+        match error.related.first() {
+            Some(x) => Self::ParseError {
+                message: x.to_string(),
+                location: Location::new(x.span().offset(), x.span().offset() + x.span().len(), filename),
+                expected: x.expected().to_vec(),
+            },
+            None => Self::ParseError {
+                message: error.to_string(),
+                location: Location::new(0, 0, filename),
+                expected: vec![],
+            },
+        }
+    }
 }
 
 /// Format an expected token message, it's useful for helpful error messages.
@@ -165,12 +290,41 @@ fn fmt_expected(expected: &[String]) -> String {
     f
 }
 
+/// Converts a lalrpop error into the matching [`InnerError`], keeping the
+/// span that will be pointed in the error message.
+fn inner_error(error: lalrpop_util::ParseError<usize, Token<'_>, InnerError>) -> InnerError {
+    use lalrpop_util::ParseError::*;
+
+    match error {
+        InvalidToken { location } => InnerError::InvalidToken {
+            err_span: SourceSpan::from(location..location),
+        },
+        UnrecognizedEof { location, expected } => InnerError::ExpectedToken {
+            err_span: SourceSpan::from(location..location),
+            help: fmt_expected(&expected),
+            expected,
+        },
+        UnrecognizedToken { token, expected } => InnerError::UnrecoginzedToken {
+            err_span: SourceSpan::from(token.0..token.2),
+            token: token.1.to_string(),
+            help: fmt_expected(&expected),
+            expected,
+        },
+        ExtraToken { ref token } => InnerError::ExtraToken {
+            err_span: SourceSpan::from(token.0..token.2),
+            token: token.1.to_string(),
+        },
+        User { error } => error,
+    }
+}
+
 /// Parses or report the error. It takes a filename to report errors and locations
 /// pointing to the file in the error message.
 ///
 /// It does return a result of a parsed file or a parse error, that can contain a
 /// lot of sub-errors.
 pub fn parse_or_report(filename: &str, text: &str) -> Result<crate::ast::File, ParseError> {
+    crate::ast::Location::register_source(filename, text);
     let mut errors = vec![];
     let ast = match crate::rinha::FileParser::new().parse(&mut errors, filename, text) {
         Ok(ast) => ast,
@@ -182,7 +336,7 @@ pub fn parse_or_report(filename: &str, text: &str) -> Result<crate::ast::File, P
             });
 
             return Err(ParseError {
-                related: vec![],
+                related: errors.into_iter().map(|recovery| inner_error(recovery.error)).collect(),
                 source_code: NamedSource::new(filename, text.to_string()),
             });
         }
@@ -195,31 +349,7 @@ pub fn parse_or_report(filename: &str, text: &str) -> Result<crate::ast::File, P
     }
 
     Err(ParseError {
-        related: errors
-            .into_iter()
-            .map(|recovery| {
-                use lalrpop_util::ParseError::*;
-
-                match recovery.error {
-                    InvalidToken { location } => InnerError::InvalidToken {
-                        err_span: SourceSpan::from(location..location),
-                    },
-                    UnrecognizedEof { location, expected } => InnerError::ExpectedToken {
-                        err_span: SourceSpan::from(location..location),
-                        help: fmt_expected(&expected),
-                    },
-                    UnrecognizedToken { token, expected } => InnerError::UnrecoginzedToken {
-                        err_span: SourceSpan::from(token.0..token.2),
-                        help: fmt_expected(&expected),
-                    },
-                    ExtraToken { ref token } => InnerError::ExtraToken {
-                        err_span: SourceSpan::from(token.0..token.2),
-                        token: token.1.to_string(),
-                    },
-                    User { error } => error,
-                }
-            })
-            .collect(),
+        related: errors.into_iter().map(|recovery| inner_error(recovery.error)).collect(),
         source_code: NamedSource::new(filename, text.to_string()),
     })
-}
\ No newline at end of file
+}
@@ -0,0 +1,91 @@
+use std::fmt::Write;
+
+use crate::ast::Term;
+
+/// Renders a term as an indented tree, one node per line, with the
+/// children of a node indented two spaces below it.
+pub fn render(term: &Term) -> String {
+    let mut out = String::new();
+    write_term(&mut out, term, 0);
+    out
+}
+
+/// Renders a term as a Graphviz DOT graph, with a node for each term
+/// labelled like a line of [`render`], and edges to its children in order:
+///
+/// ```
+/// let source = r#"let f = fn (a) => { (a, "s") }; print(f(1))"#;
+/// let file = caramuru::parser::parse_or_report("dot.rinha", source).unwrap();
+/// let dot = caramuru::printer::render_dot(&file.expression);
+/// assert!(dot.starts_with("digraph ast {"));
+/// for label in ["Let f", "Function (a)", "Tuple", "Var a", r#"Str \"s\""#, "Print", "Call", "Int 1"] {
+///     assert!(dot.contains(&format!("[label=\"{label}\"]")), "{label} missing from {dot}");
+/// }
+/// assert!(dot.contains("n0 -> n1;"));
+/// ```
+pub fn render_dot(term: &Term) -> String {
+    let mut out = String::from("digraph ast {\n  ordering=out;\n  node [shape=box];\n");
+    // Nodes are numbered in the order they're visited, with a stack of the
+    // terms left and the nodes they hang from.
+    let mut pending = vec![(term, None)];
+    let mut count = 0;
+    while let Some((term, parent)) = pending.pop() {
+        let node = count;
+        count += 1;
+        let label = label(term).replace('\\', "\\\\").replace('"', "\\\"");
+        let _ = writeln!(out, "  n{node} [label=\"{label}\"];");
+        if let Some(parent) = parent {
+            let _ = writeln!(out, "  n{parent} -> n{node};");
+        }
+        pending.extend(children(term).into_iter().rev().map(|x| (x, Some(node))));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn write_term(out: &mut String, term: &Term, depth: usize) {
+    // Writing to a String can't fail.
+    let _ = writeln!(out, "{}{}", "  ".repeat(depth), label(term));
+    for child in children(term) {
+        write_term(out, child, depth + 1);
+    }
+}
+
+/// What a term is, and the parts of it that aren't terms themselves.
+fn label(term: &Term) -> String {
+    match term {
+        Term::Error(x) => format!("Error {:?}", x.message),
+        Term::Int(x) => format!("Int {}", x.value),
+        Term::Float(x) => format!("Float {}", x.value),
+        Term::Str(x) => format!("Str {:?}", x.value),
+        Term::Bool(x) => format!("Bool {}", x.value),
+        Term::Var(x) => format!("Var {}", x.text),
+        Term::Call(_) => "Call".to_string(),
+        Term::Binary(x) => format!("Binary {}", x.op),
+        Term::Function(x) => {
+            let parameters: Vec<&str> = x.parameters.iter().map(|y| y.text.as_str()).collect();
+            format!("Function ({})", parameters.join(", "))
+        },
+        Term::Let(x) => format!("Let {}", x.name.text),
+        Term::If(_) => "If".to_string(),
+        Term::Print(_) => "Print".to_string(),
+        Term::First(_) => "First".to_string(),
+        Term::Second(_) => "Second".to_string(),
+        Term::Tuple(_) => "Tuple".to_string(),
+    }
+}
+
+fn children(term: &Term) -> Vec<&Term> {
+    match term {
+        Term::Error(_) | Term::Int(_) | Term::Float(_) | Term::Str(_) | Term::Bool(_) | Term::Var(_) => vec![],
+        Term::Call(x) => std::iter::once(&*x.callee).chain(&x.arguments).collect(),
+        Term::Binary(x) => vec![&x.lhs, &x.rhs],
+        Term::Function(x) => vec![&x.value],
+        Term::Let(x) => vec![&x.value, &x.next],
+        Term::If(x) => [&x.condition, &x.then].into_iter().chain(&x.otherwise).map(|y| &**y).collect(),
+        Term::Print(x) => vec![&x.value],
+        Term::First(x) => vec![&x.value],
+        Term::Second(x) => vec![&x.value],
+        Term::Tuple(x) => x.elements().collect(),
+    }
+}
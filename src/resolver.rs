@@ -0,0 +1,194 @@
+//! Resolves every variable reference to where its value lives before the
+//! program runs, so that evaluation indexes into frames instead of looking
+//! names up.
+
+use crate::{ast::{self, Term}, interner::{Interner, Symbol}, parser::Var};
+
+/// Where the value of a variable lives at runtime.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slot {
+    /// A top-level binding, looked up by name. Names that aren't bound
+    /// anywhere also end up here, and fail when evaluated.
+    #[default]
+    Global,
+    /// An index into the frame of the running function.
+    Local(usize),
+    /// An index into the environment captured by the running closure,
+    /// `depth` functions out: 1 is the function the closure was defined in.
+    Captured { depth: usize, index: usize },
+}
+
+/// The bindings visible in the body of a function.
+#[derive(Default)]
+struct Scope {
+    /// Names in the order they were bound, so later ones shadow earlier ones.
+    names: Vec<(String, usize)>,
+    /// Every `let` gets a slot of its own, even when it shadows another.
+    size: usize,
+}
+
+impl Scope {
+    fn bind(&mut self, name: &str) -> usize {
+        let index = self.size;
+        self.size += 1;
+        self.names.push((name.to_string(), index));
+        index
+    }
+}
+
+/// Annotates the variables of a term, and the frame sizes of the functions
+/// in it. Terms are resolved as top-level code, with the names of globals
+/// interned in `interner`.
+///
+/// Only the chain of lets the program starts with binds globals. A `let`
+/// nested in a block of the top-level code gets a fresh symbol, so the
+/// global it shadows is visible again once the block ends:
+///
+/// ```
+/// use caramuru::RuntimeValue;
+///
+/// let source = "let x = 1; let y = { let x = 2; x * 10 }; x + y";
+/// assert_eq!(caramuru::eval_str(source).unwrap(), RuntimeValue::Int(21));
+/// let source = "let f = fn () => { let x = 1; let y = if (true) { let x = 2; x } else { 0 }; x + y }; f()";
+/// assert_eq!(caramuru::eval_str(source).unwrap(), RuntimeValue::Int(3));
+/// ```
+///
+/// Top-level code runs once, so those bindings can live with the globals.
+pub fn resolve(term: &Term, interner: &mut Interner) {
+    Resolver { scopes: vec![], blocks: vec![], spine: true, interner }.term(term)
+}
+
+struct Resolver<'a> {
+    /// The functions enclosing the term being resolved, innermost last.
+    scopes: Vec<Scope>,
+    /// The names bound in blocks of the top-level code, and their fresh
+    /// symbols, in the order they were bound.
+    blocks: Vec<(String, Symbol)>,
+    /// Whether the term being resolved is in the chain of top-level lets
+    /// rather than nested in another term.
+    spine: bool,
+    interner: &'a mut Interner,
+}
+
+impl Resolver<'_> {
+    fn lookup(&self, name: &str) -> Slot {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some((_, index)) = scope.names.iter().rev().find(|(x, _)| x == name) {
+                return match depth {
+                    0 => Slot::Local(*index),
+                    _ => Slot::Captured { depth, index: *index },
+                }
+            }
+        }
+        Slot::Global
+    }
+
+    fn var(&mut self, var: &Var) {
+        match self.lookup(&var.text) {
+            Slot::Global => self.global(var),
+            slot => var.slot.set(slot),
+        }
+    }
+
+    fn global(&mut self, var: &Var) {
+        let symbol = match self.blocks.iter().rev().find(|(x, _)| *x == var.text) {
+            Some((_, symbol)) => *symbol,
+            None => self.interner.intern(&var.text),
+        };
+        var.slot.set(Slot::Global);
+        var.symbol.set(symbol);
+    }
+
+    /// Resolves a function body in a scope of its own. Its frame starts with
+    /// the closure itself when `name` is given, then the parameters.
+    fn function(&mut self, function: &ast::Function, name: Option<&str>) {
+        let mut scope = Scope::default();
+        if let Some(x) = name {
+            scope.bind(x);
+        }
+        for parameter in &function.parameters {
+            scope.bind(&parameter.text);
+        }
+        self.scopes.push(scope);
+        self.term(&function.value);
+        if let Some(scope) = self.scopes.pop() {
+            function.frame_size.set(scope.size);
+        }
+    }
+
+    fn term(&mut self, term: &Term) {
+        // Every term below this one is nested in it.
+        let spine = std::mem::replace(&mut self.spine, false);
+        match term {
+            Term::Error(_) | Term::Int(_) | Term::Float(_) | Term::Str(_) | Term::Bool(_) => {},
+            Term::Var(x) => self.var(x),
+            Term::Let(_) => {
+                // Chains of lets are walked in a loop rather than recursing
+                // once per binding, like in the interpreter.
+                let mut term = term;
+                let mut bound = 0;
+                while let Term::Let(x) = term {
+                    match &*x.value {
+                        // Closures can't see their own binding in what they
+                        // capture, which predates it, so they get it in their
+                        // frame instead.
+                        Term::Function(y) => self.function(y, Some(&x.name.text)),
+                        value => self.term(value),
+                    }
+                    match self.scopes.last_mut() {
+                        Some(scope) => x.name.slot.set(Slot::Local(scope.bind(&x.name.text))),
+                        None if spine => {
+                            x.name.slot.set(Slot::Global);
+                            x.name.symbol.set(self.interner.intern(&x.name.text));
+                        },
+                        None => self.block(&x.name),
+                    }
+                    bound += 1;
+                    term = &x.next;
+                }
+                self.spine = spine;
+                self.term(term);
+                match self.scopes.last_mut() {
+                    Some(scope) => scope.names.truncate(scope.names.len() - bound),
+                    None if !spine => self.blocks.truncate(self.blocks.len() - bound),
+                    None => {},
+                }
+            },
+            Term::Function(x) => self.function(x, None),
+            Term::Call(x) => {
+                self.term(&x.callee);
+                for argument in &x.arguments {
+                    self.term(argument);
+                }
+            },
+            Term::Binary(x) => {
+                self.term(&x.lhs);
+                self.term(&x.rhs);
+            },
+            Term::If(x) => {
+                self.term(&x.condition);
+                self.term(&x.then);
+                if let Some(otherwise) = &x.otherwise {
+                    self.term(otherwise);
+                }
+            },
+            Term::Print(x) => self.term(&x.value),
+            Term::First(x) => self.term(&x.value),
+            Term::Second(x) => self.term(&x.value),
+            Term::Tuple(x) => {
+                for element in x.elements() {
+                    self.term(element);
+                }
+            },
+        }
+        self.spine = spine;
+    }
+
+    /// Binds a `let` in a block of the top-level code to a fresh symbol.
+    fn block(&mut self, var: &Var) {
+        let symbol = self.interner.fresh(&var.text);
+        var.slot.set(Slot::Global);
+        var.symbol.set(symbol);
+        self.blocks.push((var.text.to_string(), symbol));
+    }
+}
@@ -0,0 +1,94 @@
+//! Checks that a syntax tree is one the interpreter can run, so that a
+//! malformed JSON AST is reported before anything is evaluated rather
+//! than halfway through.
+
+use crate::ast::{self, Term};
+
+/// Something wrong with a syntax tree, and where it is.
+#[derive(Debug)]
+pub struct Problem {
+    pub message: String,
+    pub location: ast::Location,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.message, self.location)
+    }
+}
+
+/// Lists the problems of a term, in the order they appear in it.
+pub fn validate(term: &Term) -> Vec<Problem> {
+    let mut problems = vec![];
+    let mut problem = |message: String, location: &ast::Location| {
+        problems.push(Problem { message, location: location.clone() })
+    };
+    // Terms are walked with a stack of their own, since generated ASTs can
+    // nest deeper than the native stack allows.
+    let mut pending = vec![term];
+    while let Some(term) = pending.pop() {
+        let children: Vec<&Term> = match term {
+            Term::Error(x) => {
+                problem(format!("parse error: {}", x.message), &x.location);
+                vec![]
+            },
+            Term::Int(_) | Term::Float(_) | Term::Str(_) | Term::Bool(_) => vec![],
+            Term::Var(x) => {
+                if x.text.is_empty() {
+                    problem("variable with an empty name".to_string(), &x.location);
+                }
+                vec![]
+            },
+            Term::Call(x) => {
+                if matches!(&*x.callee, Term::Int(_) | Term::Float(_) | Term::Str(_) | Term::Bool(_) | Term::Tuple(_)) {
+                    problem("callee is not a function".to_string(), &x.location);
+                }
+                std::iter::once(&*x.callee).chain(&x.arguments).collect()
+            },
+            Term::Binary(x) => vec![&x.lhs, &x.rhs],
+            Term::Function(x) => {
+                for (i, parameter) in x.parameters.iter().enumerate() {
+                    if parameter.text.is_empty() {
+                        problem("parameter with an empty name".to_string(), &parameter.location);
+                    } else if x.parameters[..i].iter().any(|y| y.text == parameter.text) {
+                        problem(format!("parameter \"{}\" is declared twice", parameter.text), &parameter.location);
+                    }
+                }
+                vec![&x.value]
+            },
+            Term::Let(x) => {
+                if x.name.text.is_empty() {
+                    problem("binding with an empty name".to_string(), &x.name.location);
+                }
+                vec![&x.value, &x.next]
+            },
+            Term::If(x) => [&x.condition, &x.then].into_iter().chain(&x.otherwise).map(|y| &**y).collect(),
+            Term::Print(x) => vec![&x.value],
+            Term::First(x) => {
+                if !is_pair_like(&x.value) {
+                    problem("first applied to a value that is not a pair".to_string(), &x.location);
+                }
+                vec![&x.value]
+            },
+            Term::Second(x) => {
+                if !is_pair_like(&x.value) {
+                    problem("second applied to a value that is not a pair".to_string(), &x.location);
+                }
+                vec![&x.value]
+            },
+            Term::Tuple(x) => x.elements().collect(),
+        };
+        // Children are pushed in reverse so they're visited in order.
+        pending.extend(children.into_iter().rev());
+    }
+    problems
+}
+
+/// Whether a term might evaluate to a pair. Only literals are known not to.
+fn is_pair_like(term: &Term) -> bool {
+    match term {
+        Term::Int(_) | Term::Float(_) | Term::Str(_) | Term::Bool(_) | Term::Function(_) => false,
+        Term::Tuple(x) => x.rest.is_empty(),
+        _ => true,
+    }
+}
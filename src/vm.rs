@@ -0,0 +1,497 @@
+//! A bytecode compiler and stack machine, an alternative to the
+//! tree-walking evaluator for programs that spend their time in hot calls.
+//!
+//! Terms are resolved as for the interpreter, then every function body is
+//! lowered to a flat list of [`Instruction`]s. Values, closures and
+//! environments are the interpreter's own, so both agree on what a program
+//! prints.
+
+use std::{collections::HashMap, io::Write, rc::Rc};
+
+use crate::{
+    as_condition, as_logical, ast, eval_binary_op, interner::Interner, intrinsics, parser,
+    resolver::{self, Slot},
+    BacktraceFrame, Closure, Env, EvalResult, Globals, Interpreter, InterpreterError, RuntimeValue,
+};
+
+/// An operation of the stack machine. Indices refer to the tables of the
+/// [`Program`] the instruction belongs to.
+#[derive(Clone, Copy, Debug)]
+pub enum Instruction {
+    Int(i32),
+    Bool(bool),
+    /// Pushes a copy of a constant.
+    Const(usize),
+    /// Pushes a slot of the running frame. `var` is the variable read.
+    LoadLocal { index: usize, var: usize },
+    /// Pushes a slot of the environment captured by the running closure.
+    LoadCaptured { depth: usize, index: usize, var: usize },
+    LoadGlobal(usize),
+    /// Pops a value into a slot of the running frame.
+    StoreLocal(usize),
+    StoreGlobal(usize),
+    /// Pops two operands and pushes the result of the operator.
+    Binary(ast::BinaryOp),
+    /// Replaces the top of the stack by its truth value as an operand of
+    /// `&&` or `||`.
+    Logical,
+    Jump(usize),
+    /// Pops a condition and jumps when it's false.
+    JumpIfFalse(usize),
+    /// Pushes a closure for a function, named after the variable that a
+    /// `let` binds it to.
+    Closure { function: usize, name: Option<usize> },
+    /// Pops `argc` arguments and calls the function bound to `callee`.
+    Call { callee: usize, argc: usize },
+    /// A call in tail position, which replaces the running frame.
+    TailCall { callee: usize, argc: usize },
+    /// Pops `argc` arguments and calls the function below them, for callees
+    /// that aren't variables. `callee` names it `<anonymous>`.
+    CallValue { callee: usize, argc: usize },
+    /// Prints the value on top of the stack, which is also the value of
+    /// the `print`.
+    Print,
+    /// Pops a pair and pushes its first element. The operand is the
+    /// location reported when the value is not a pair.
+    First(usize),
+    Second(usize),
+    /// Pops `n` values and pushes a tuple of them.
+    Tuple(usize),
+    /// Fails with a parse error kept in the AST.
+    ParseError(usize),
+    /// Ends the running function with the value on top of the stack.
+    Return,
+}
+
+/// Compiled code, along with the tables its instructions refer to.
+#[derive(Default)]
+pub struct Program {
+    /// Chunk 0 is the top-level code, the rest are function bodies.
+    chunks: Vec<Vec<Instruction>>,
+    constants: Vec<RuntimeValue>,
+    vars: Vec<parser::Var>,
+    functions: Vec<ast::Function>,
+    locations: Vec<ast::Location>,
+    errors: Vec<ast::Error>,
+    /// The chunk of each function, by the address of its shared body.
+    bodies: HashMap<*const ast::Term, usize>,
+}
+
+impl Program {
+    /// Finds the chunk of a closure's body, compiling it when the closure
+    /// was created outside of this program.
+    fn chunk_of(&mut self, closure: &Closure) -> usize {
+        match self.bodies.get(&Rc::as_ptr(&closure.function.value)) {
+            Some(x) => *x,
+            None => self.function(&closure.function),
+        }
+    }
+
+    fn function(&mut self, function: &ast::Function) -> usize {
+        let chunk = self.chunks.len();
+        self.chunks.push(vec![]);
+        self.bodies.insert(Rc::as_ptr(&function.value), chunk);
+        let mut code = vec![];
+        self.term(&mut code, &function.value, true);
+        code.push(Instruction::Return);
+        self.chunks[chunk] = code;
+        chunk
+    }
+
+    fn constant(&mut self, value: RuntimeValue) -> Instruction {
+        self.constants.push(value);
+        Instruction::Const(self.constants.len() - 1)
+    }
+
+    fn var(&mut self, var: &parser::Var) -> usize {
+        self.vars.push(var.clone());
+        self.vars.len() - 1
+    }
+
+    fn location(&mut self, location: &ast::Location) -> usize {
+        self.locations.push(location.clone());
+        self.locations.len() - 1
+    }
+
+    fn closure(&mut self, code: &mut Vec<Instruction>, function: &ast::Function, name: Option<&parser::Var>) {
+        self.function(function);
+        self.functions.push(function.clone());
+        let name = name.map(|x| self.var(x));
+        code.push(Instruction::Closure { function: self.functions.len() - 1, name });
+    }
+
+    /// Compiles a term that leaves its value on the stack. Calls in `tail`
+    /// position become tail calls.
+    fn term(&mut self, code: &mut Vec<Instruction>, term: &ast::Term, tail: bool) {
+        match term {
+            ast::Term::Error(x) => {
+                self.errors.push(x.clone());
+                code.push(Instruction::ParseError(self.errors.len() - 1));
+            },
+            ast::Term::Int(x) => code.push(Instruction::Int(x.value)),
+            ast::Term::Float(x) => code.push(self.constant(RuntimeValue::Float(x.value))),
+            ast::Term::Str(x) => code.push(self.constant(RuntimeValue::Str(x.value.to_string()))),
+            ast::Term::Bool(x) => code.push(Instruction::Bool(x.value)),
+            ast::Term::Var(x) => {
+                let var = self.var(x);
+                code.push(match x.slot.get() {
+                    Slot::Global => Instruction::LoadGlobal(var),
+                    Slot::Local(index) => Instruction::LoadLocal { index, var },
+                    Slot::Captured { depth, index } => Instruction::LoadCaptured { depth, index, var },
+                });
+            },
+            ast::Term::Let(_) => {
+                let mut term = term;
+                while let ast::Term::Let(x) = term {
+                    match &*x.value {
+                        ast::Term::Function(y) => self.closure(code, y, Some(&x.name)),
+                        value => self.term(code, value, false),
+                    }
+                    code.push(match x.name.slot.get() {
+                        Slot::Local(index) => Instruction::StoreLocal(index),
+                        _ => Instruction::StoreGlobal(self.var(&x.name)),
+                    });
+                    term = &x.next;
+                }
+                self.term(code, term, tail);
+            },
+            ast::Term::If(x) => {
+                self.term(code, &x.condition, false);
+                let jump_otherwise = code.len();
+                code.push(Instruction::JumpIfFalse(0));
+                self.term(code, &x.then, tail);
+                let jump_end = code.len();
+                code.push(Instruction::Jump(0));
+                code[jump_otherwise] = Instruction::JumpIfFalse(code.len());
+                match &x.otherwise {
+                    Some(otherwise) => self.term(code, otherwise, tail),
+                    None => {
+                        let void = self.constant(RuntimeValue::Void(()));
+                        code.push(void);
+                    },
+                }
+                code[jump_end] = Instruction::Jump(code.len());
+            },
+            ast::Term::Binary(x) => match x.op {
+                // Logical operators only evaluate the right side when it decides the result.
+                ast::BinaryOp::And | ast::BinaryOp::Or => {
+                    self.term(code, &x.lhs, false);
+                    code.push(Instruction::Logical);
+                    let jump_short = code.len();
+                    code.push(Instruction::JumpIfFalse(0));
+                    let is_and = matches!(x.op, ast::BinaryOp::And);
+                    if is_and {
+                        self.term(code, &x.rhs, false);
+                        code.push(Instruction::Logical);
+                    } else {
+                        code.push(Instruction::Bool(true));
+                    }
+                    let jump_end = code.len();
+                    code.push(Instruction::Jump(0));
+                    code[jump_short] = Instruction::JumpIfFalse(code.len());
+                    if is_and {
+                        code.push(Instruction::Bool(false));
+                    } else {
+                        self.term(code, &x.rhs, false);
+                        code.push(Instruction::Logical);
+                    }
+                    code[jump_end] = Instruction::Jump(code.len());
+                },
+                op => {
+                    self.term(code, &x.lhs, false);
+                    self.term(code, &x.rhs, false);
+                    code.push(Instruction::Binary(op));
+                },
+            },
+            ast::Term::Function(x) => self.closure(code, x, None),
+            ast::Term::Call(x) => {
+                let var = match &*x.callee {
+                    ast::Term::Var(callee) => Some(callee),
+                    callee => {
+                        self.term(code, callee, false);
+                        None
+                    },
+                };
+                for argument in &x.arguments {
+                    self.term(code, argument, false);
+                }
+                let argc = x.arguments.len();
+                code.push(match var {
+                    Some(callee) => {
+                        let callee = self.var(callee);
+                        match tail {
+                            true => Instruction::TailCall { callee, argc },
+                            false => Instruction::Call { callee, argc },
+                        }
+                    },
+                    None => {
+                        let callee = self.var(&parser::Var::anonymous(ast::Element::location(&*x.callee)));
+                        Instruction::CallValue { callee, argc }
+                    },
+                });
+            },
+            ast::Term::Print(x) => {
+                self.term(code, &x.value, false);
+                code.push(Instruction::Print);
+            },
+            ast::Term::First(x) => {
+                self.term(code, &x.value, false);
+                code.push(Instruction::First(self.location(&x.location)));
+            },
+            ast::Term::Second(x) => {
+                self.term(code, &x.value, false);
+                code.push(Instruction::Second(self.location(&x.location)));
+            },
+            ast::Term::Tuple(x) => {
+                let mut n = 0;
+                for element in x.elements() {
+                    self.term(code, element, false);
+                    n += 1;
+                }
+                code.push(Instruction::Tuple(n));
+            },
+        }
+    }
+}
+
+/// Resolves and compiles a top-level term, interning the names of its
+/// globals in `interner`.
+pub fn compile(expr: &ast::Term, interner: &mut Interner) -> Program {
+    resolver::resolve(expr, interner);
+    let mut program = Program::default();
+    program.chunks.push(vec![]);
+    let mut code = vec![];
+    program.term(&mut code, expr, false);
+    code.push(Instruction::Return);
+    program.chunks[0] = code;
+    program
+}
+
+/// A function running on the machine.
+struct Frame {
+    chunk: usize,
+    /// The next instruction.
+    ip: usize,
+    slots: Vec<RuntimeValue>,
+    env: Option<Rc<Env>>,
+    /// The globals as the running closure saw them when it was defined.
+    globals: Option<Rc<Globals>>,
+    /// The variable the function was called through, `None` for the
+    /// top-level code.
+    callee: Option<usize>,
+}
+
+impl Frame {
+    /// Reads a global, as the running closure sees it.
+    fn global<'a>(&'a self, interpreter: &'a Interpreter, var: &parser::Var) -> Option<&'a RuntimeValue> {
+        self.globals.as_ref()
+            .and_then(|x| x.get(&var.symbol.get()))
+            .or_else(|| interpreter.call_stack.globals.get(&var.symbol.get()))
+    }
+}
+
+fn undefined(var: &parser::Var) -> InterpreterError {
+    InterpreterError::UndefinedVariable { name: var.text.to_string(), location: var.location.clone() }
+}
+
+/// Pops a value the compiler made sure is there.
+fn pop(stack: &mut Vec<RuntimeValue>) -> RuntimeValue {
+    stack.pop().unwrap_or(RuntimeValue::Void(()))
+}
+
+impl Interpreter {
+    /// Compiles a top-level term and runs it on the stack machine. Globals
+    /// and output are shared with [`Interpreter::eval`], but tracing,
+    /// memoization and statistics are not supported.
+    pub fn eval_vm(&mut self, expr: &ast::Term) -> EvalResult {
+        let mut program = compile(expr, &mut self.call_stack.interner);
+        let mut frame = Frame { chunk: 0, ip: 0, slots: vec![], env: None, globals: None, callee: None };
+        let mut callers = vec![];
+        self.backtrace.clear();
+        let result = self.run(&mut program, &mut frame, &mut callers);
+        if result.is_err() {
+            let frames = std::iter::once(&frame).chain(callers.iter().rev());
+            self.backtrace = frames
+                .filter_map(|x| x.callee.map(|y| &program.vars[y]))
+                .map(|x| BacktraceFrame { name: x.text.to_string(), location: x.location.clone() })
+                .collect();
+        }
+        result
+    }
+
+    fn run(&mut self, program: &mut Program, frame: &mut Frame, callers: &mut Vec<Frame>) -> EvalResult {
+        let mut stack = vec![];
+        loop {
+            let instruction = program.chunks[frame.chunk][frame.ip];
+            frame.ip += 1;
+            self.burn_fuel()?;
+            match instruction {
+                Instruction::Int(x) => stack.push(RuntimeValue::Int(x)),
+                Instruction::Bool(x) => stack.push(RuntimeValue::Bool(x)),
+                Instruction::Const(x) => stack.push(program.constants[x].clone()),
+                Instruction::LoadLocal { index, var } => {
+                    let value = frame.slots.get(index).ok_or_else(|| undefined(&program.vars[var]))?;
+                    stack.push(value.clone());
+                },
+                Instruction::LoadCaptured { depth, index, var } => {
+                    let value = frame.env.as_ref().and_then(|x| x.get(depth, index));
+                    stack.push(value.ok_or_else(|| undefined(&program.vars[var]))?.clone());
+                },
+                Instruction::LoadGlobal(var) => {
+                    let var = &program.vars[var];
+                    let value = frame.global(self, var).ok_or_else(|| undefined(var))?;
+                    stack.push(value.clone());
+                },
+                Instruction::StoreLocal(index) => {
+                    let value = pop(&mut stack);
+                    if let Some(x) = frame.slots.get_mut(index) {
+                        *x = value;
+                    }
+                },
+                Instruction::StoreGlobal(var) => {
+                    let value = pop(&mut stack);
+                    self.call_stack.globals.insert(program.vars[var].symbol.get(), value);
+                },
+                Instruction::Binary(op) => {
+                    let r = pop(&mut stack);
+                    let l = pop(&mut stack);
+                    stack.push(eval_binary_op(op, l, r)?);
+                },
+                Instruction::Logical => {
+                    let value = as_logical(&pop(&mut stack))?;
+                    stack.push(RuntimeValue::Bool(value));
+                },
+                Instruction::Jump(x) => frame.ip = x,
+                Instruction::JumpIfFalse(x) => {
+                    if !as_condition(pop(&mut stack), self.config.lenient)? {
+                        frame.ip = x;
+                    }
+                },
+                Instruction::Closure { function, name } => {
+                    // Closures defined in the top-level code capture nothing.
+                    let env = match callers.is_empty() {
+                        true => None,
+                        false => Some(Rc::new(Env {
+                            vars: frame.slots.to_vec(),
+                            parent: frame.env.clone(),
+                        })),
+                    };
+                    let globals = frame.globals.clone().unwrap_or_else(|| self.call_stack.snapshot_globals());
+                    let name = name.map(|x| program.vars[x].text.to_string());
+                    let function = program.functions[function].clone();
+                    stack.push(RuntimeValue::Function(Rc::new(Closure { function, env, globals, name })));
+                },
+                Instruction::Call { callee, argc }
+                | Instruction::TailCall { callee, argc }
+                | Instruction::CallValue { callee, argc } => {
+                    let var = &program.vars[callee];
+                    let closure = match instruction {
+                        // The callee was pushed before the arguments.
+                        Instruction::CallValue { .. } => match stack.remove(stack.len() - argc - 1) {
+                            RuntimeValue::Function(x) => x,
+                            x => return Err(InterpreterError::type_mismatch(
+                                format!("callee is not a function, found {}", x.type_name())
+                            )),
+                        },
+                        _ => {
+                            let value = match var.slot.get() {
+                                Slot::Global => frame.global(self, var),
+                                Slot::Local(index) => frame.slots.get(index),
+                                Slot::Captured { depth, index } => frame.env.as_ref().and_then(|x| x.get(depth, index)),
+                            };
+                            match value {
+                                Some(RuntimeValue::Function(x)) => x.clone(),
+                                Some(_) => return Err(InterpreterError::type_mismatch(format!("\"{}\" is not a function", var.text))),
+                                None => match intrinsics::lookup(&var.text, &self.config) {
+                                    Some(intrinsic) => {
+                                        if intrinsic.arity.is_some_and(|x| x != argc) {
+                                            return Err(InterpreterError::ArityMismatch {
+                                                name: intrinsic.name.to_string(),
+                                                expected: intrinsic.arity.unwrap_or_default(),
+                                                actual: argc,
+                                                location: var.location.clone(),
+                                            });
+                                        }
+                                        let arguments = stack.split_off(stack.len() - argc);
+                                        stack.push((intrinsic.call)(arguments, &var.location, self)?);
+                                        continue
+                                    },
+                                    None => return Err(undefined(var)),
+                                },
+                            }
+                        },
+                    };
+                    if argc != closure.function.parameters.len() {
+                        return Err(InterpreterError::ArityMismatch {
+                            name: var.text.to_string(),
+                            expected: closure.function.parameters.len(),
+                            actual: argc,
+                            location: var.location.clone(),
+                        });
+                    }
+                    let tail = matches!(instruction, Instruction::TailCall { .. });
+                    if !tail && callers.len() + 1 > self.config.max_depth {
+                        return Err(InterpreterError::StackOverflow {
+                            name: var.text.to_string(),
+                            max_depth: self.config.max_depth,
+                        });
+                    }
+                    let frame_size = closure.function.frame_size.get();
+                    let mut slots = Vec::with_capacity(frame_size);
+                    if closure.name.is_some() {
+                        slots.push(RuntimeValue::Function(closure.clone()));
+                    }
+                    slots.extend(stack.drain(stack.len() - argc..));
+                    // The `let` slots are filled as the body runs.
+                    slots.resize_with(slots.len().max(frame_size), || RuntimeValue::Void(()));
+                    let next = Frame {
+                        chunk: program.chunk_of(&closure),
+                        ip: 0,
+                        slots,
+                        env: closure.env.clone(),
+                        globals: Some(closure.globals.clone()),
+                        callee: Some(callee),
+                    };
+                    match tail {
+                        true => *frame = next,
+                        false => callers.push(std::mem::replace(frame, next)),
+                    }
+                },
+                Instruction::Print => {
+                    if let Some(value) = stack.last() {
+                        write!(self.output, "{value}")?;
+                    }
+                },
+                Instruction::First(location) | Instruction::Second(location) => {
+                    let (index, name) = match instruction {
+                        Instruction::First(_) => (0, "first"),
+                        _ => (1, "second"),
+                    };
+                    match pop(&mut stack).as_tuple() {
+                        Ok(y @ [_, _]) => stack.push(y[index].clone()),
+                        _ => return Err(InterpreterError::type_mismatch_at(
+                            format!("{name} applied to a value that is not a pair"),
+                            &program.locations[location],
+                        )),
+                    }
+                },
+                Instruction::Tuple(n) => {
+                    let elements = stack.split_off(stack.len() - n);
+                    stack.push(RuntimeValue::Tuple(elements));
+                },
+                Instruction::ParseError(x) => {
+                    let error = &program.errors[x];
+                    return Err(InterpreterError::ParseError {
+                        message: error.message.to_string(),
+                        location: error.location.clone(),
+                        expected: vec![],
+                    })
+                },
+                Instruction::Return => match callers.pop() {
+                    Some(caller) => *frame = caller,
+                    None => return Ok(pop(&mut stack)),
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! The typed accessors of `RuntimeValue`, and the operators reading their
+//! operands through them.
+
+mod common;
+
+use caramuru::RuntimeValue;
+use common::{run, run_vm};
+
+#[test]
+fn each_accessor_reads_its_own_type() {
+    assert_eq!(RuntimeValue::Int(7).as_int().unwrap(), 7);
+    assert_eq!(RuntimeValue::Str("seven".to_string()).as_str().unwrap(), "seven");
+    assert!(RuntimeValue::Bool(true).as_bool().unwrap());
+    let tuple = RuntimeValue::Tuple(vec![RuntimeValue::Int(1), RuntimeValue::Bool(false)]);
+    assert_eq!(tuple.as_tuple().unwrap(), [RuntimeValue::Int(1), RuntimeValue::Bool(false)]);
+}
+
+#[test]
+fn each_accessor_fails_on_other_types() {
+    let message = |x: caramuru::InterpreterError| x.to_string();
+    assert_eq!(message(RuntimeValue::Str("7".to_string()).as_int().unwrap_err()), "type mismatch: expected an int, found str");
+    assert_eq!(message(RuntimeValue::Int(7).as_str().unwrap_err()), "type mismatch: expected a str, found int");
+    assert_eq!(message(RuntimeValue::Int(1).as_bool().unwrap_err()), "type mismatch: expected a bool, found int");
+    assert_eq!(message(RuntimeValue::Bool(true).as_tuple().unwrap_err()), "type mismatch: expected a tuple, found bool");
+}
+
+#[test]
+fn first_and_second_read_pairs_only() {
+    for run in [run, run_vm] {
+        assert_eq!(run("(first((1, 2)), second((1, 2)))").value(), "(1, 2)");
+        assert!(run("first(1)").error().contains("first applied to a value that is not a pair"));
+        assert!(run("second((1, 2, 3))").error().contains("second applied to a value that is not a pair"));
+    }
+}
+
+#[test]
+fn arithmetic_and_logic_keep_their_messages() {
+    for run in [run, run_vm] {
+        assert_eq!(run("(7 - 2, 1 && true, false || 0)").value(), "(5, true, false)");
+        assert_eq!(run("true - 1").error(), "type mismatch: operator - expects integer operands");
+        assert_eq!(run("\"a\" < 1").error(), "type mismatch: operator < expects two numbers or two strings");
+        assert_eq!(run("\"a\" && true").error(), "type mismatch: logical operand is not a boolean or an integer");
+    }
+}
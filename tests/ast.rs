@@ -0,0 +1,61 @@
+//! The syntax tree as JSON, the way the rinha spec lays it out.
+
+use serde_json::{json, Value};
+
+/// A location in `every.rinha`.
+fn at(start: usize, end: usize) -> Value {
+    json!({"start": start, "end": end, "filename": "every.rinha"})
+}
+
+/// A file using every kind of term, with every location different.
+fn every_term() -> Value {
+    let int = |value: i32, start| json!({"kind": "Int", "value": value, "location": at(start, start + 1)});
+    let var = |text: &str, start| json!({"kind": "Var", "text": text, "location": at(start, start + 1)});
+    json!({
+        "name": "every.rinha",
+        "expression": {
+            "kind": "Let",
+            "name": {"text": "f", "location": at(4, 5)},
+            "value": {
+                "kind": "Function",
+                "parameters": [{"text": "a", "location": at(12, 13)}, {"text": "b", "location": at(15, 16)}],
+                "value": {"kind": "Binary", "lhs": var("a", 20), "op": "Add", "rhs": var("b", 24), "location": at(20, 25)},
+                "location": at(8, 27),
+            },
+            "next": {
+                "kind": "Print",
+                "value": {
+                    "kind": "Tuple",
+                    "first": {"kind": "Call", "callee": var("f", 35), "arguments": [int(1, 37), int(2, 40)], "location": at(35, 42)},
+                    "second": {"kind": "First", "value": {"kind": "Tuple", "first": {"kind": "Str", "value": "s", "location": at(50, 53)}, "second": {"kind": "Float", "value": 1.5, "location": at(55, 58)}, "location": at(49, 59)}, "location": at(43, 60)},
+                    "rest": [
+                        {"kind": "Second", "value": var("t", 69), "location": at(62, 71)},
+                        {"kind": "If", "condition": {"kind": "Bool", "value": true, "location": at(76, 80)}, "then": int(3, 84), "otherwise": int(4, 95), "location": at(73, 97)},
+                        {"kind": "If", "condition": {"kind": "Bool", "value": false, "location": at(102, 107)}, "then": int(5, 111), "location": at(99, 113)},
+                        {"kind": "Error", "message": "unexpected token", "full_text": "?", "location": at(115, 116)},
+                    ],
+                    "location": at(34, 117),
+                },
+                "location": at(28, 118),
+            },
+            "location": at(0, 118),
+        },
+        "location": at(0, 118),
+    })
+}
+
+#[test]
+fn every_term_round_trips_through_the_ast() {
+    let json = every_term();
+    let file: caramuru::ast::File = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&file).unwrap(), json);
+}
+
+#[test]
+fn parsed_source_round_trips_through_json() {
+    let source = "let f = fn (a, b) => { if (a < b) { (a, b, \"c\") } else { first((b, a)) } };\nprint(second(f(1, 2.5)))";
+    let file = caramuru::parser::parse_or_report("source.rinha", source).unwrap();
+    let json = serde_json::to_string(&file).unwrap();
+    let reparsed: caramuru::ast::File = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&reparsed).unwrap(), json);
+}
@@ -0,0 +1,75 @@
+//! Blocks of statements separated by `;`, and the `let`s in them.
+
+mod common;
+
+use caramuru::{parser, Config};
+use common::{interpreter, run, run_vm, Output};
+
+#[test]
+fn statements_run_in_order_and_the_last_one_is_the_value() {
+    let source = r#"{ print("a"); print("b"); 1 + 1 }"#;
+    for run in [run, run_vm] {
+        let run = run(source);
+        assert_eq!(run.output, "ab");
+        assert_eq!(run.value(), "2");
+    }
+}
+
+#[test]
+fn lets_in_a_block_in_a_function_end_with_it() {
+    let source = "let f = fn () => { let x = 1; let y = { let x = 2; x }; (x, y) }; f()";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "(1, 2)");
+    }
+}
+
+#[test]
+fn branches_and_function_bodies_are_blocks() {
+    let source = "let f = fn (n) => { print(n); if (n > 0) { print(\"+\"); n } else { 0 } }; f(3)";
+    for run in [run, run_vm] {
+        let run = run(source);
+        assert_eq!(run.output, "3+");
+        assert_eq!(run.value(), "3");
+    }
+}
+
+#[test]
+fn lets_in_a_top_level_block_end_with_it() {
+    let source = "let x = 1; let y = { let x = 2; x }; (x, y)";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "(1, 2)");
+    }
+}
+
+#[test]
+fn a_shadowing_let_in_a_branch_leaves_the_outer_binding() {
+    let source = "let x = 1; let y = if (true) { let x = 3; x } else { 0 }; (x, y)";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "(1, 3)");
+    }
+}
+
+#[test]
+fn nested_blocks_in_a_function_restore_each_shadowed_local() {
+    let source = "let f = fn (x) => { let a = { let x = x * 10; { let x = x + 1; x } }; (a, x) }; let x = \"global\"; (f(2), x)";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "((21, 2), global)");
+    }
+}
+
+#[test]
+fn a_closure_made_in_a_block_keeps_the_shadowing_binding() {
+    let source = "let x = 1; let g = { let x = 2; fn () => { x } }; (g(), x)";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "(2, 1)");
+    }
+}
+
+#[test]
+fn lets_in_a_top_level_block_are_not_listed_as_globals() {
+    let mut interpreter = interpreter(Config::default(), &Output::default());
+    let file = parser::parse_or_report("test.rinha", "let x = 1; let y = { let z = 2; let x = 3; z + x }; y").unwrap();
+    interpreter.eval(&file.expression).unwrap();
+    let globals: Vec<_> = interpreter.call_stack.globals().into_iter().map(|(x, y)| format!("{x} = {y}")).collect();
+    assert_eq!(globals, ["x = 1", "y = 5"]);
+}
@@ -0,0 +1,73 @@
+//! How calls evaluate their arguments.
+
+mod common;
+
+use caramuru::{InterpreterError, RuntimeValue};
+use common::{run, run_vm};
+
+#[test]
+fn arguments_are_evaluated_once_from_left_to_right() {
+    let run = run("
+        let f = fn (a, b, c) => { a + b + c };
+        let p = fn (x) => { let _ = print(x); x };
+        f(p(1), p(2), p(3))
+    ");
+    assert_eq!(run.output, "123");
+    assert!(matches!(run.value, Ok(RuntimeValue::Int(6))));
+}
+
+#[test]
+fn arguments_are_evaluated_in_the_frame_of_the_caller() {
+    let source = "
+        let n = 10;
+        let f = fn (n) => { n * 2 };
+        let g = fn (m) => { f(m + n) };
+        g(1)
+    ";
+    assert_eq!(run(source).value(), "22");
+}
+
+#[test]
+fn arguments_can_be_calls_and_closures() {
+    let source = "
+        let apply = fn (f, x) => { f(x) };
+        let twice = fn (x) => { x * 2 };
+        apply(fn (x) => { twice(twice(x)) }, apply(twice, 3))
+    ";
+    assert_eq!(run(source).value(), "24");
+}
+
+#[test]
+fn a_failing_argument_stops_the_call() {
+    let run = run("let f = fn (a, b) => { print(a + b) }; f(print(1), zz)");
+    assert_eq!(run.output, "1");
+    assert!(matches!(run.value, Err(InterpreterError::UndefinedVariable { name, .. }) if name == "zz"));
+}
+
+#[test]
+fn too_few_or_too_many_arguments_fail_rather_than_curry() {
+    let source = |arguments| format!("let f = fn (a, b) => {{ a + b }};\nf({arguments})");
+    for run in [run, run_vm] {
+        for (arguments, count) in [("1", 1), ("1, 2, 3", 3)] {
+            let source = source(arguments);
+            let error = run(&source).value.expect_err("the call should fail");
+            assert_eq!(error.to_string(), format!("wrong number of arguments passed to f: expected 2, got {count}"));
+            assert_eq!(error.location().unwrap().line_column(&source), (2, 1));
+        }
+    }
+}
+
+#[test]
+fn function_expressions_can_be_called_where_they_are_made() {
+    for run in [run, run_vm] {
+        assert_eq!(run("print((fn (x) => { x * 2 })(5))").output, "10");
+        assert_eq!(run("(fn (x) => fn (y) => x + y)(1)(2)").value(), "3");
+    }
+}
+
+#[test]
+fn calling_what_is_not_a_function_is_an_error() {
+    for run in [run, run_vm] {
+        assert_eq!(run("(1)(2)").error(), "type mismatch: callee is not a function, found int");
+    }
+}
@@ -0,0 +1,454 @@
+//! The command line, run as the binary.
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+/// A file under the system's temporary directory holding `contents`, named
+/// so tests running at the same time don't share it.
+fn temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("caramuru-{}-{name}", std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn caramuru(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_caramuru")).args(args).output().unwrap()
+}
+
+/// Runs the binary with `input` piped into its stdin.
+fn caramuru_with_input(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_caramuru"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+/// `data` in a gzip file, as the `gzip` tool would write it.
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg());
+        }
+    }
+    let mut bytes = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3];
+    bytes.extend(miniz_oxide::deflate::compress_to_vec(data, 6));
+    bytes.extend((!crc).to_le_bytes());
+    bytes.extend((data.len() as u32).to_le_bytes());
+    bytes
+}
+
+const HELLO_JSON: &str = r#"{
+    "name": "hello.json",
+    "expression": {
+        "kind": "Print",
+        "value": {"kind": "Str", "value": "hello", "location": {"start": 6, "end": 13, "filename": "hello.json"}},
+        "location": {"start": 0, "end": 14, "filename": "hello.json"}
+    },
+    "location": {"start": 0, "end": 14, "filename": "hello.json"}
+}"#;
+
+#[test]
+fn runs_the_json_ast_at_the_given_path() {
+    let path = temp_file("hello.json", HELLO_JSON.as_bytes());
+    let output = caramuru(&[path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "hello");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn fails_on_a_path_that_does_not_exist() {
+    let output = caramuru(&["/nonexistent/program.json"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("error: could not open \"/nonexistent/program.json\""));
+}
+
+#[test]
+fn reads_a_json_ast_from_stdin_given_a_dash() {
+    let output = caramuru_with_input(&["-"], HELLO_JSON.as_bytes());
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "hello");
+}
+
+#[test]
+fn usage_tells_about_the_dash() {
+    let output = caramuru(&[]);
+    assert!(stderr(&output).contains("pass - to read a JSON AST from stdin"));
+}
+
+#[test]
+fn runs_a_source_file_end_to_end() {
+    let path = temp_file("square.rinha", b"let square = fn (x) => { x * x };\nprint(square(12))\n");
+    let output = caramuru(&[path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "144");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn repl_keeps_bindings_between_lines() {
+    let output = caramuru_with_input(&["--repl"], b"let x = 20; x\n\nlet f = fn (y) => { x + y }; f(1)\nf(2) * 2\n");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "> 20\n> > 21\n> 44\n> ");
+}
+
+#[test]
+fn repl_reports_errors_and_goes_on() {
+    let output = caramuru_with_input(&["--repl", "--no-color"], b"zz\n1 +\n2\n");
+    assert_eq!(stdout(&output), "> \n> > 2\n> ");
+    assert!(stderr(&output).contains("error: undefined variable \"zz\""));
+    assert!(stderr(&output).contains("expected token, but got eof"));
+}
+
+#[test]
+fn a_failing_program_exits_with_its_error() {
+    let path = temp_file("mismatch.rinha", b"print(1 + true)\n");
+    let output = caramuru(&[path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("type mismatch: operator + expects integer operands"), "{}", stderr(&output));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn runtime_errors_point_at_their_line_and_column() {
+    let path = temp_file("undefined.rinha", b"let x = 1;\n  x + zz\n");
+    let output = caramuru(&[path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains(&format!("--> {}:2:7", path.display())), "{}", stderr(&output));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn max_depth_limits_the_call_depth() {
+    let path = temp_file("forever.rinha", b"let forever = fn (n) => { 1 + forever(n + 1) };\nforever(0)\n");
+    let output = caramuru(&["--max-depth", "100", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("stack overflow: call depth exceeded 100 calling forever"), "{}", stderr(&output));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn repl_closures_keep_the_binding_they_were_defined_after() {
+    let output = caramuru_with_input(&["--repl"], b"let x = 1; x\nlet f = fn () => { x }; f()\nlet x = 2; x\nf()\n");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "> 1\n> 1\n> 2\n> 1\n> ");
+}
+
+#[test]
+fn memoize_skips_functions_that_print() {
+    let path = temp_file("dots.rinha", b"let f = fn (n) => { let _ = print(\".\"); n };\n(f(1), f(1))\n");
+    let output = caramuru(&["--memoize", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "..");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn memoize_tells_a_redefined_function_from_the_old_one() {
+    // The REPL parses every line as the same file from offset 0, so both
+    // functions start at the same location.
+    let output = caramuru_with_input(&["--repl", "--memoize"], b"let f = fn (x) => { x + 1 }; f(1)\nlet f = fn (x) => { x + 4 }; f(1)\n");
+    assert_eq!(stdout(&output), "> 2\n> 5\n> ");
+}
+
+#[test]
+fn output_printed_before_an_error_is_written() {
+    let path = temp_file("partial.rinha", b"let _ = print(\"before\");\n1 + true\n");
+    let output = caramuru(&[path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "before");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn ast_prints_the_tree_without_running_it() {
+    let path = temp_file("tree.rinha", b"let x = print(1);\nif (x == 1) { (x, \"a\") } else { first((x, 2)) }");
+    let output = caramuru(&["--ast", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let expected = "Let x
+  Print
+    Int 1
+  If
+    Binary ==
+      Var x
+      Int 1
+    Tuple
+      Var x
+      Str \"a\"
+    First
+      Tuple
+        Var x
+        Int 2
+";
+    assert_eq!(stdout(&output), expected);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn emit_json_round_trips_the_parsed_tree_with_its_locations() {
+    let source = "let f = fn (n) => n + 1;\nprint(f(2))";
+    let path = temp_file("emit.rinha", source.as_bytes());
+    let output = caramuru(&["--emit-json", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let emitted: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(emitted["expression"]["next"]["location"]["start"], 25);
+
+    let json = temp_file("emit.json", stdout(&output).as_bytes());
+    let rerun = caramuru(&[json.to_str().unwrap()]);
+    assert_eq!(stdout(&rerun), "3");
+    let reemitted = caramuru(&["--emit-json", json.to_str().unwrap()]);
+    assert_eq!(stdout(&reemitted), stdout(&output));
+    std::fs::remove_file(json).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn decompresses_a_gzipped_json_ast() {
+    let path = temp_file("hello.json.gz", &gzip(HELLO_JSON.as_bytes()));
+    let output = caramuru(&[path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "hello");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn decompresses_every_member_of_a_gzip_file() {
+    let (head, tail) = HELLO_JSON.as_bytes().split_at(HELLO_JSON.len() / 2);
+    let mut bytes = gzip(head);
+    bytes.extend(gzip(tail));
+    let path = temp_file("members.json.gz", &bytes);
+    let output = caramuru(&[path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "hello");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn rejects_a_gzip_file_with_a_bad_checksum() {
+    let mut bytes = gzip(HELLO_JSON.as_bytes());
+    let crc = bytes.len() - 8;
+    bytes[crc] ^= 1;
+    let path = temp_file("bad_crc.json.gz", &bytes);
+    let output = caramuru(&[path.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("ChecksumMismatch"), "{}", stderr(&output));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn trace_logs_nested_calls_to_stderr() {
+    let path = temp_file("trace.rinha", b"let inc = fn (n) => n + 1;\nlet twice = fn (n) => inc(inc(n));\nprint(twice(1))");
+    let output = caramuru(&["--trace", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "3");
+    assert_eq!(stderr(&output), "twice(1)\n  inc(1)\n  inc = 2\n  inc(2)\n  inc = 3\ntwice = 3\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn time_prints_how_long_the_evaluation_took() {
+    let path = temp_file("time.rinha", b"print(1)");
+    let output = caramuru(&["--time", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "1");
+    let stderr = stderr(&output);
+    let line = stderr.strip_suffix('\n').unwrap();
+    let duration = line.strip_prefix("time: ").unwrap();
+    assert!(duration.ends_with('s') && duration.starts_with(|x: char| x.is_ascii_digit()), "{line}");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn args_bind_globals_the_program_reads() {
+    let path = temp_file("args.rinha", b"print((n + 1, name, flag))");
+    let output = caramuru(&["--arg", "n=10", "--arg", "name=ana", "--arg", "flag=true", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "(11, ana, true)");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn an_arg_without_a_value_is_a_usage_error() {
+    let output = caramuru(&["--arg", "n", "program.rinha"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("error: invalid --arg \"n\", expected name=value\n"));
+}
+
+#[test]
+fn get_env_reads_set_variables_and_unset_ones_as_empty() {
+    let path = temp_file("env.rinha", br#"print((get_env("CARAMURU_SET"), get_env("CARAMURU_UNSET") == ""))"#);
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_caramuru"))
+            .args(args)
+            .arg(&path)
+            .env("CARAMURU_SET", "hi")
+            .env_remove("CARAMURU_UNSET")
+            .output()
+            .unwrap()
+    };
+    for args in [&[][..], &["--vm"]] {
+        let output = run(args);
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(stdout(&output), "(hi, true)");
+    }
+
+    let output = run(&["--no-env"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with("error: undefined variable \"get_env\""));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn check_accepts_a_valid_program_without_running_it() {
+    let path = temp_file("valid.rinha", b"let x = 1;\nprint(x + 2)");
+    let output = caramuru(&["--check", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(stderr(&output), "");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn check_lists_the_problems_of_an_invalid_program() {
+    let path = temp_file("invalid.rinha", b"let f = fn (a, a) => { first(1) };\nf(1, 2)");
+    let path = path.to_str().unwrap();
+    let output = caramuru(&["--check", path]);
+    assert_eq!(output.status.code(), Some(1));
+    let expected = format!(
+        "{path}:1:16: parameter \"a\" is declared twice\n{path}:1:24: first applied to a value that is not a pair\n"
+    );
+    assert_eq!(stderr(&output), expected);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn json_errors_print_the_failure_as_an_object() {
+    let path = temp_file("json-errors.rinha", b"let g = fn (n) => { missing };\nlet f = fn (n) => { g(n) + 1 };\nf(1)");
+    let expected = serde_json::json!({
+        "kind": "UndefinedVariable",
+        "message": "undefined variable \"missing\"",
+        "location": {"filename": path.to_str().unwrap(), "start": 20, "end": 27},
+        "backtrace": ["g", "f"],
+        "expected": [],
+    });
+    for vm in [&[][..], &["--vm"]] {
+        let output = caramuru(&[vm, &["--json-errors", path.to_str().unwrap()]].concat());
+        assert_eq!(output.status.code(), Some(1));
+        let error: serde_json::Value = serde_json::from_str(&stderr(&output)).unwrap();
+        assert_eq!(error, expected);
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn color_never_writes_errors_without_escapes() {
+    let runtime = temp_file("color-runtime.rinha", b"missing");
+    let syntax = temp_file("color-syntax.rinha", b"let x = ;");
+    for path in [&runtime, &syntax] {
+        let plain = caramuru(&["--color=never", path.to_str().unwrap()]);
+        assert!(!plain.stderr.is_empty());
+        assert!(!plain.stderr.contains(&0x1b), "{}", stderr(&plain));
+        assert_eq!(caramuru(&["--no-color", path.to_str().unwrap()]).stderr, plain.stderr);
+        assert!(caramuru(&["--color=always", path.to_str().unwrap()]).stderr.contains(&0x1b));
+    }
+    std::fs::remove_file(runtime).unwrap();
+    std::fs::remove_file(syntax).unwrap();
+}
+
+#[test]
+fn errors_in_source_name_the_path_it_was_read_from() {
+    let path = temp_file("where.rinha", b"missing");
+    let output = caramuru(&[path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains(&format!("--> {}:1:1\n", path.display())), "{}", stderr(&output));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn errors_in_a_json_ast_name_the_file_it_was_compiled_from() {
+    let location = r#""location": {"start": 0, "end": 7, "filename": "original.rinha"}"#;
+    let json = format!(r#"{{"name": "original.rinha", "expression": {{"kind": "Var", "text": "missing", {location}}}, {location}}}"#);
+    let path = temp_file("where.json", json.as_bytes());
+    let output = caramuru(&[path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("--> original.rinha, byte 0"), "{}", stderr(&output));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn dump_scope_prints_the_top_level_bindings_by_name() {
+    let path = temp_file("scope.rinha", b"let b = \"two\";\nlet a = 1;\nlet c = (a, b);\nprint(a)");
+    let output = caramuru(&["--dump-scope", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "1");
+    assert_eq!(stderr(&output), "a = 1\nb = two\nc = (1, two)\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn syntax_errors_show_the_line_and_what_was_expected() {
+    let path = temp_file("syntax.rinha", b"let x = ;");
+    let output = caramuru(&["--color=never", path.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(stderr.contains("unrecognized token `;`"), "{stderr}");
+    assert!(stderr.contains(" 1 │ let x = ;\n"), "{stderr}");
+    assert!(stderr.contains("help: expected one of \"(\""), "{stderr}");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn graphviz_prints_a_node_for_each_term() {
+    let path = temp_file("graph.rinha", b"let f = fn (a) => { if (a < 2) { (a, \"s\") } else { first((a, true)) } };\nprint(f(1))");
+    let output = caramuru(&["--graphviz", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let dot = stdout(&output);
+    assert!(dot.starts_with("digraph ast {\n") && dot.ends_with("}\n"), "{dot}");
+    let labels: Vec<_> = dot.lines().filter_map(|x| x.split_once("[label=\"")).map(|(_, x)| x.trim_end_matches("\"];")).collect();
+    let expected = [
+        "Let f", "Function (a)", "If", "Binary <", "Var a", "Int 2", "Tuple", "Var a", r#"Str \"s\""#, "First", "Tuple",
+        "Var a", "Bool true", "Print", "Call", "Var f", "Int 1",
+    ];
+    assert_eq!(labels, expected);
+    assert_eq!(dot.matches(" -> ").count(), expected.len() - 1);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn read_line_reads_stdin_a_line_at_a_time_with_allow_io() {
+    let path = temp_file("lines.rinha", b"print((read_line(), read_line(), read_line(), read_line()))");
+    for backend in [&[][..], &["--vm"]] {
+        let output = caramuru_with_input(&[backend, &["--allow-io", path.to_str().unwrap()]].concat(), b"one\r\n\nthree");
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(stdout(&output), "(one, , three, )");
+    }
+    let output = caramuru_with_input(&[path.to_str().unwrap()], b"one\n");
+    assert!(stderr(&output).contains("undefined variable \"read_line\""), "{}", stderr(&output));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn detect_loops_does_not_report_calls_reading_lines() {
+    let path = temp_file("reading.rinha", b"let count = fn (n) => { if (read_line() == \"end\") { n } else { count(n) + 1 } };\nprint(count(0))");
+    let output = caramuru_with_input(&["--allow-io", "--detect-loops", path.to_str().unwrap()], b"a\nb\nend\n");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "2");
+    std::fs::remove_file(path).unwrap();
+}
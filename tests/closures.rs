@@ -0,0 +1,58 @@
+//! Functions that use the bindings around where they were defined.
+
+mod common;
+
+use common::{run, run_vm};
+
+#[test]
+fn two_level_curried_adder() {
+    let source = "
+        let add = fn (a) => { fn (b) => { fn (c) => { a + b + c } } };
+        let add_one = add(1);
+        let add_three = add_one(2);
+        let add_ten = add(10);
+        let add_thirty = add_ten(20);
+        (add_three(3), add_thirty(30))
+    ";
+    assert_eq!(run(source).value(), "(6, 60)");
+}
+
+#[test]
+fn nested_function_returns_a_parameter_of_the_outer_one() {
+    let source = "let f = fn (a) => { let g = fn () => { a }; g() }; f(7)";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "7");
+    }
+}
+
+#[test]
+fn nested_function_combines_its_parameters_with_the_outer_ones() {
+    let source = "let add = fn (a) => { let g = fn (b) => { a + b }; g(10) }; add(32)";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "42");
+    }
+}
+
+#[test]
+fn returned_closure_outlives_the_call_that_made_it() {
+    let source = "
+        let counter = fn (start) => { fn (step) => { start + step } };
+        let from_ten = counter(10);
+        let from_twenty = counter(20);
+        (from_ten(1), from_twenty(2), from_ten(3))
+    ";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "(11, 22, 13)");
+    }
+}
+
+#[test]
+fn closure_keeps_the_value_a_local_had_when_it_was_made() {
+    let source = "
+        let f = fn (x) => { let g = fn () => { x }; let x = x + 1; (g(), x) };
+        f(1)
+    ";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "(1, 2)");
+    }
+}
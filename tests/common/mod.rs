@@ -0,0 +1,109 @@
+//! Helpers shared by the integration tests, which run programs from source
+//! and look at what they evaluate to and what they print.
+
+#![allow(dead_code)]
+
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+use caramuru::{global_frame, parser, Config, EvalResult, Interpreter};
+
+/// A writer into a buffer that stays readable after the writer is handed
+/// over to an [`Interpreter`].
+#[derive(Clone, Default)]
+pub struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Output {
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// What running a program gave.
+pub struct Run {
+    pub value: EvalResult,
+    pub output: String,
+}
+
+impl Run {
+    /// The value the program evaluated to, as `print` would write it.
+    pub fn value(&self) -> String {
+        match &self.value {
+            Ok(x) => x.to_string(),
+            Err(e) => panic!("the program failed: {e}"),
+        }
+    }
+
+    /// The message of the error the program failed with.
+    pub fn error(&self) -> String {
+        match &self.value {
+            Ok(x) => panic!("the program didn't fail, it gave {x}"),
+            Err(e) => e.to_string(),
+        }
+    }
+}
+
+/// An interpreter printing into `output`, with its global frame pushed.
+pub fn interpreter(config: Config, output: &Output) -> Interpreter {
+    let mut interpreter = Interpreter::with_output(config, Box::new(output.clone()));
+    interpreter.call_stack.push(global_frame());
+    interpreter
+}
+
+/// Parses and runs a program, on the VM when `vm` is set.
+pub fn run_with(source: &str, config: Config, vm: bool) -> Run {
+    let file = parser::parse_or_report("test.rinha", source).expect("the program should parse");
+    let output = Output::default();
+    let mut interpreter = interpreter(config, &output);
+    let value = match vm {
+        true => interpreter.eval_vm(&file.expression),
+        false => interpreter.eval(&file.expression),
+    };
+    Run { value, output: output.text() }
+}
+
+/// Runs a program with the default configuration.
+pub fn run(source: &str) -> Run {
+    run_with(source, Config::default(), false)
+}
+
+/// Runs a program on the VM with the default configuration.
+pub fn run_vm(source: &str) -> Run {
+    run_with(source, Config::default(), true)
+}
+
+/// Deserializes a file whose JSON AST has `expression` as its expression,
+/// whose terms can leave out their locations as `{loc}`, which this fills in.
+pub fn file_json(expression: &str) -> caramuru::ast::File {
+    let location = r#""location": {"start": 0, "end": 0, "filename": "test.json"}"#;
+    let json = format!(r#"{{"name": "test.json", "expression": {}, {location}}}"#, expression.replace("{loc}", location));
+    serde_json::from_str(&json).expect("the AST should deserialize")
+}
+
+/// Runs a program given as the `expression` of a JSON AST, as in
+/// [`file_json`].
+pub fn run_json(expression: &str) -> Run {
+    let file = file_json(expression);
+    let output = Output::default();
+    let value = interpreter(Config::default(), &output).eval(&file.expression);
+    Run { value, output: output.text() }
+}
+
+/// Runs `test` on a thread with a stack as large as the one `main` runs
+/// programs on, for programs that recurse deeply.
+pub fn with_large_stack<T: Send + 'static>(test: impl FnOnce() -> T + Send + 'static) -> T {
+    std::thread::Builder::new()
+        .stack_size(1 << 30)
+        .spawn(test)
+        .unwrap()
+        .join()
+        .unwrap()
+}
@@ -0,0 +1,51 @@
+//! What `if` accepts as a condition.
+
+mod common;
+
+use caramuru::{Config, RuntimeValue};
+use common::{file_json, interpreter, run, run_vm, run_with, Output};
+
+#[test]
+fn bool_conditions_pick_a_branch() {
+    for run in [run, run_vm] {
+        assert_eq!(run(r#"(if (true) { "yes" } else { "no" }, if (false) { "yes" } else { "no" })"#).value(), "(yes, no)");
+    }
+}
+
+#[test]
+fn lenient_conditions_take_nonzero_integers_as_true() {
+    let lenient = Config { lenient: true, ..Config::default() };
+    for vm in [false, true] {
+        let run = run_with(r#"(if (1) { "yes" } else { "no" }, if (0) { "yes" } else { "no" })"#, lenient.clone(), vm);
+        assert_eq!(run.value(), "(yes, no)");
+    }
+}
+
+#[test]
+fn strict_conditions_must_be_bools() {
+    for run in [run, run_vm] {
+        assert_eq!(run(r#"if (1) { "yes" } else { "no" }"#).error(), "type mismatch: expected a bool condition, found int");
+    }
+}
+
+/// What an `if` without an `else` evaluates to on both executors, given its
+/// condition.
+fn else_less(condition: bool) -> RuntimeValue {
+    let file = file_json(&format!(
+        r#"{{"kind": "If", "condition": {{"kind": "Bool", "value": {condition}, {{loc}}}}, "then": {{"kind": "Int", "value": 1, {{loc}}}}, {{loc}}}}"#
+    ));
+    let value = interpreter(Config::default(), &Output::default()).eval(&file.expression).unwrap();
+    let on_vm = interpreter(Config::default(), &Output::default()).eval_vm(&file.expression).unwrap();
+    assert_eq!(on_vm, value);
+    value
+}
+
+#[test]
+fn if_without_else_is_void_when_false() {
+    assert_eq!(else_less(false), RuntimeValue::Void(()));
+}
+
+#[test]
+fn if_without_else_runs_its_branch_when_true() {
+    assert_eq!(else_less(true), RuntimeValue::Int(1));
+}
@@ -0,0 +1,53 @@
+//! The errors bad programs fail with, rather than panicking.
+
+mod common;
+
+use caramuru::{Config, InterpreterError};
+use common::{interpreter, run, Output};
+
+#[test]
+fn bad_programs_fail_with_the_error_for_what_they_did() {
+    assert!(matches!(run("1 + true").value, Err(InterpreterError::TypeMismatch { .. })));
+    assert!(matches!(run("if (\"yes\") { 1 } else { 2 }").value, Err(InterpreterError::TypeMismatch { .. })));
+    assert!(matches!(run("let f = 1; f()").value, Err(InterpreterError::TypeMismatch { .. })));
+    assert!(matches!(run("x").value, Err(InterpreterError::UndefinedVariable { name, .. }) if name == "x"));
+    assert!(matches!(
+        run("let f = fn (a) => { a }; f()").value,
+        Err(InterpreterError::ArityMismatch { expected: 1, actual: 0, .. })
+    ));
+}
+
+#[test]
+fn undefined_variables_carry_where_they_were_referenced() {
+    let run = run("let f = fn () => {\n  missing\n};\nf()");
+    assert_eq!(run.error(), "undefined variable \"missing\"");
+    assert!(matches!(run.value, Err(InterpreterError::UndefinedVariable { location, .. }) if (location.start, location.end) == (21, 28)));
+}
+
+#[test]
+fn an_error_term_in_the_ast_fails_with_its_message_and_location() {
+    let location = r#"{"start": 8, "end": 11, "filename": "broken.json"}"#;
+    let json = format!(
+        r#"{{"name": "broken.json", "expression": {{"kind": "Error", "message": "unexpected token", "full_text": "???", "location": {location}}}, "location": {location}}}"#
+    );
+    let file: caramuru::ast::File = serde_json::from_str(&json).unwrap();
+    let error = caramuru::eval(&file.expression).expect_err("the program should fail");
+    assert_eq!(error.to_string(), "parse error: unexpected token");
+    assert_eq!(error.location().map(|x| (x.start, x.end)), Some((8, 11)));
+}
+
+#[test]
+fn the_backtrace_lists_the_calls_an_error_returned_through() {
+    let source = "let g = fn (n) => { n + true };\nlet f = fn (n) => { g(n) + 1 };\nf(1)";
+    let file = caramuru::parser::parse_or_report("test.rinha", source).unwrap();
+    for vm in [false, true] {
+        let mut interpreter = interpreter(Config::default(), &Output::default());
+        let result = match vm {
+            true => interpreter.eval_vm(&file.expression),
+            false => interpreter.eval(&file.expression),
+        };
+        assert!(result.is_err());
+        let backtrace: Vec<_> = interpreter.backtrace.iter().map(|x| (x.name.as_str(), x.location.line_column(source))).collect();
+        assert_eq!(backtrace, [("g", (2, 21)), ("f", (3, 1))], "on the vm: {vm}");
+    }
+}
@@ -0,0 +1,49 @@
+//! The programs under `examples/` and `ast-examples/`, run by the binary
+//! like `run-examples` does, against what they should print.
+
+use std::process::Command;
+
+fn assert_prints(path: &str, expected: &str) {
+    let path = format!("{}/{path}", env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO_BIN_EXE_caramuru")).arg(&path).output().unwrap();
+    assert!(output.status.success(), "{path} failed: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected, "{path}");
+}
+
+#[test]
+fn hello() {
+    assert_prints("examples/hello.rinha", "Hello World");
+}
+
+#[test]
+fn fib() {
+    assert_prints("examples/fib.rinha", "55");
+    assert_prints("ast-examples/fib.json", "55");
+}
+
+#[test]
+fn sum() {
+    assert_prints("examples/sum.rinha", "15");
+    assert_prints("ast-examples/sum.json", "15");
+}
+
+#[test]
+fn combination() {
+    assert_prints("examples/combination.rinha", "45");
+    assert_prints("ast-examples/combination.json", "45");
+}
+
+#[test]
+fn closure() {
+    assert_prints("examples/closure.rinha", "42");
+}
+
+#[test]
+fn factorial() {
+    assert_prints("examples/factorial.rinha", "3628800");
+}
+
+#[test]
+fn block() {
+    assert_prints("examples/block.rinha", "n = 7odd");
+}
@@ -0,0 +1,65 @@
+//! Numbering names with symbols, and the globals stored under them.
+
+mod common;
+
+use caramuru::{interner::Interner, parser, Config, RuntimeValue};
+use common::{interpreter, run, run_vm, with_large_stack, Output};
+
+#[test]
+fn the_same_name_gets_the_same_symbol() {
+    let mut interner = Interner::default();
+    let x = interner.intern("x");
+    let y = interner.intern("y");
+    assert_ne!(x, y);
+    assert_eq!(interner.intern("x"), x);
+    assert_eq!(interner.intern("y"), y);
+    assert_eq!((interner.name(x), interner.name(y)), ("x", "y"));
+}
+
+#[test]
+fn fresh_symbols_are_never_interned() {
+    let mut interner = Interner::default();
+    let x = interner.intern("x");
+    let fresh = interner.fresh("x");
+    assert_ne!(fresh, x);
+    assert_eq!(interner.name(fresh), "x");
+    assert_eq!(interner.intern("x"), x);
+    assert!(interner.is_interned(x) && !interner.is_interned(fresh));
+}
+
+#[test]
+fn globals_set_before_the_program_are_read_by_name() {
+    for vm in [false, true] {
+        let output = Output::default();
+        let mut interpreter = interpreter(Config::default(), &output);
+        interpreter.call_stack.set_global("answer", RuntimeValue::Int(42));
+        let file = parser::parse_or_report("test.rinha", "answer + 1").unwrap();
+        let value = match vm {
+            true => interpreter.eval_vm(&file.expression),
+            false => interpreter.eval(&file.expression),
+        };
+        assert_eq!(value.unwrap(), RuntimeValue::Int(43));
+    }
+}
+
+#[test]
+fn globals_outlive_the_program_that_bound_them() {
+    let output = Output::default();
+    let mut interpreter = interpreter(Config::default(), &output);
+    for source in ["let x = 1; let y = 2; x", "let z = 3; z", "print(x + y + z)"] {
+        let file = parser::parse_or_report("repl", source).unwrap();
+        interpreter.eval(&file.expression).unwrap();
+    }
+    assert_eq!(output.text(), "6");
+}
+
+#[test]
+fn many_globals_keep_their_own_values() {
+    with_large_stack(|| {
+        let bindings: String = (0..500).map(|i| format!("let v{i} = {i};")).collect();
+        let sum = (0..500).map(|i| format!("v{i}")).collect::<Vec<_>>().join(" + ");
+        for run in [run, run_vm] {
+            assert_eq!(run(&format!("{bindings} {sum}")).value(), "124750");
+        }
+    })
+}
@@ -0,0 +1,158 @@
+//! The [`Interpreter`] and the entry points around it, used as a library.
+
+mod common;
+
+use caramuru::{parser, Config, InterpreterError, RuntimeValue};
+use common::{interpreter, run, run_vm, run_with, Output};
+
+#[test]
+fn print_writes_exactly_the_values_to_the_output() {
+    let run = run(r#"let _ = print("é"); let _ = print((1, true)); print(-2)"#);
+    assert!(run.value.is_ok());
+    assert_eq!(run.output.as_bytes(), "é(1, true)-2".as_bytes());
+}
+
+#[test]
+fn print_evaluates_to_its_argument() {
+    for run in [run, run_vm] {
+        let bound = run("let x = print(42); x + 1");
+        assert!(matches!(bound.value, Ok(RuntimeValue::Int(43))));
+        assert_eq!(bound.output, "42");
+        assert!(matches!(run("print(42) + 1").value, Ok(RuntimeValue::Int(43))));
+    }
+}
+
+#[test]
+fn an_interpreter_evaluates_a_program_and_unwinds_its_calls() {
+    let output = Output::default();
+    let mut interpreter = interpreter(Config::default(), &output);
+    let file = parser::parse_or_report("test.rinha", "let double = fn (x) => { x * 2 }; print(double(21))").unwrap();
+    assert!(interpreter.eval(&file.expression).is_ok());
+    assert_eq!(interpreter.call_stack.len(), 1);
+    assert_eq!(output.text(), "42");
+}
+
+/// The statistics of running `source`.
+fn stats(source: &str) -> caramuru::Stats {
+    let mut interpreter = interpreter(Config { stats: true, ..Config::default() }, &Output::default());
+    let file = parser::parse_or_report("test.rinha", source).unwrap();
+    interpreter.eval(&file.expression).unwrap();
+    interpreter.stats.unwrap()
+}
+
+#[test]
+fn stats_count_every_call_and_the_deepest_nesting() {
+    let stats = stats("let fib = fn (n) => if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }; fib(10)");
+    assert_eq!(stats.calls, 177);
+    assert_eq!(stats.max_depth, 10);
+    assert!(stats.evals > stats.calls);
+}
+
+#[test]
+fn stats_count_tail_calls_without_nesting_them() {
+    let stats = stats("let count = fn (n) => if (n == 0) { 0 } else { count(n - 1) }; count(100)");
+    assert_eq!(stats.calls, 101);
+    assert_eq!(stats.max_depth, 1);
+}
+
+#[test]
+fn run_json_returns_what_the_program_printed() {
+    let location = r#""location": {"start": 0, "end": 0, "filename": "hello.json"}"#;
+    let json = format!(
+        r#"{{"name": "hello.json", "expression": {{"kind": "Print", "value": {{"kind": "Str", "value": "hello", {location}}}, {location}}}, {location}}}"#
+    );
+    assert_eq!(caramuru::run_json(&json).unwrap(), "hello");
+}
+
+#[test]
+fn run_json_fails_on_what_is_not_an_ast() {
+    assert!(matches!(caramuru::run_json(r#"{"name": "x.json"}"#), Err(InterpreterError::InvalidAst(_))));
+}
+
+#[test]
+fn eval_evaluates_a_term_with_a_default_interpreter() {
+    let file = parser::parse_or_report("test.rinha", "let x = 40; (x + 2, x)").unwrap();
+    assert_eq!(caramuru::eval(&file.expression).unwrap().to_string(), "(42, 40)");
+}
+
+#[test]
+fn eval_str_parses_and_evaluates_source() {
+    assert!(matches!(caramuru::eval_str("let x = 1; x + 2"), Ok(RuntimeValue::Int(3))));
+    assert_eq!(caramuru::eval_str("let f = fn (a, b) => { (b, a) }; f(1, 2)").unwrap().to_string(), "(2, 1)");
+}
+
+#[test]
+fn eval_str_reports_syntax_errors() {
+    assert!(matches!(caramuru::eval_str("let x = ;"), Err(InterpreterError::ParseError { .. })));
+}
+
+#[test]
+fn a_reset_stack_runs_another_program_without_its_variables() {
+    let output = Output::default();
+    let mut interpreter = interpreter(Config::default(), &output);
+    let first = parser::parse_or_report("first.rinha", "let x = 1; let f = fn () => { x }; print(f())").unwrap();
+    interpreter.eval(&first.expression).unwrap();
+
+    interpreter.call_stack.reset();
+    assert_eq!(interpreter.call_stack.len(), 1);
+    let second = parser::parse_or_report("second.rinha", "let y = 2; print(y)").unwrap();
+    interpreter.eval(&second.expression).unwrap();
+    assert_eq!(output.text(), "12");
+    let third = parser::parse_or_report("third.rinha", "f()").unwrap();
+    assert!(matches!(interpreter.eval(&third.expression), Err(InterpreterError::UndefinedVariable { .. })));
+}
+
+#[test]
+fn running_out_of_fuel_aborts_an_endless_loop() {
+    let source = "let spin = fn (n) => { spin(n + 1) }; print(spin(0))";
+    let config = Config { fuel: Some(1000), ..Config::default() };
+    for vm in [false, true] {
+        let run = run_with(source, config.clone(), vm);
+        assert_eq!(run.error(), "out of fuel: the program took more than 1000 steps");
+        assert_eq!(run.output, "");
+    }
+}
+
+#[test]
+fn a_program_within_its_fuel_runs_to_the_end() {
+    let config = Config { fuel: Some(1000), ..Config::default() };
+    for vm in [false, true] {
+        assert_eq!(run_with("let f = fn (n) => { n + 1 }; f(1)", config.clone(), vm).value(), "2");
+    }
+}
+
+#[test]
+fn the_profile_times_each_function_called() {
+    let source = "let fib = fn (n) => if (n < 2) { n } else { fib(n - 1) + fib(n - 2) }; let run = fn () => fib(12); run()";
+    let mut interpreter = interpreter(Config { profile: true, ..Config::default() }, &Output::default());
+    let file = parser::parse_or_report("test.rinha", source).unwrap();
+    interpreter.eval(&file.expression).unwrap();
+    let profile = interpreter.profile.unwrap();
+    let (fib, run) = (&profile.functions["fib"], &profile.functions["run"]);
+    assert_eq!((fib.calls, run.calls), (465, 1));
+    assert!(run.own <= run.inclusive && fib.inclusive <= run.inclusive);
+
+    let table = profile.to_string();
+    let rows: Vec<_> = table.lines().map(|x| x.split_whitespace().take(2).collect::<Vec<_>>()).collect();
+    assert_eq!(rows[0], ["function", "calls"]);
+    assert!(rows.contains(&vec!["fib", "465"]) && rows.contains(&vec!["run", "1"]), "{table}");
+}
+
+#[test]
+fn globals_come_out_in_the_same_order_on_every_run() {
+    let names: Vec<String> = (0..50).map(|i| format!("v{}", (i * 37) % 50)).collect();
+    let source: String = names.iter().map(|x| format!("let {x} = 1;\n")).collect::<String>() + "0";
+    let globals = || {
+        let mut interpreter = interpreter(Config::default(), &Output::default());
+        let file = parser::parse_or_report("test.rinha", &source).unwrap();
+        interpreter.eval(&file.expression).unwrap();
+        interpreter.call_stack.globals().into_iter().map(|(x, _)| x.to_string()).collect::<Vec<_>>()
+    };
+    let first = globals();
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(first, sorted);
+    for _ in 0..5 {
+        assert_eq!(globals(), first);
+    }
+}
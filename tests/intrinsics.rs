@@ -0,0 +1,213 @@
+//! The functions every program can call without defining them.
+
+mod common;
+
+use caramuru::{parser, Config, EvalResult, RuntimeValue};
+use common::{interpreter, run, run_vm, Output};
+
+#[test]
+fn to_int_parses_decimal_integers() {
+    assert!(matches!(run(r#"to_int("42")"#).value, Ok(RuntimeValue::Int(42))));
+    assert!(matches!(run(r#"to_int("-7")"#).value, Ok(RuntimeValue::Int(-7))));
+    assert!(matches!(run("to_int(\" 5\n\")").value, Ok(RuntimeValue::Int(5))));
+}
+
+#[test]
+fn to_int_fails_on_what_is_not_an_integer() {
+    assert_eq!(run(r#"to_int("abc")"#).error(), r#"invalid argument passed to to_int: "abc" is not an integer"#);
+    assert_eq!(run("to_int(5)").error(), "type mismatch: to_int expects a string");
+}
+
+#[test]
+fn programs_can_define_their_own_to_int() {
+    assert_eq!(run("let to_int = fn (x) => x + 1; to_int(1)").value(), "2");
+}
+
+#[test]
+fn to_str_gives_the_text_print_writes() {
+    assert!(matches!(run("to_str(42)").value, Ok(RuntimeValue::Str(x)) if x == "42"));
+    assert!(matches!(run("to_str(true)").value, Ok(RuntimeValue::Str(x)) if x == "true"));
+    assert!(matches!(run("to_str((1, 2))").value, Ok(RuntimeValue::Str(x)) if x == "(1, 2)"));
+    assert_eq!(run(r#"to_str(fn (x) => x) + "!""#).value(), "<#closure>!");
+}
+
+#[test]
+fn len_counts_the_elements_of_a_tuple() {
+    assert!(matches!(run("len((1, 2))").value, Ok(RuntimeValue::Int(2))));
+    assert!(matches!(run("len((1, (2, 3), 4))").value, Ok(RuntimeValue::Int(3))));
+}
+
+#[test]
+fn len_fails_on_what_is_not_a_tuple() {
+    for run in [run, run_vm] {
+        assert_eq!(run("len(1)").error(), "type mismatch: len expects a tuple or an array");
+    }
+}
+
+#[test]
+fn mod_is_euclidean_while_rem_truncates() {
+    for run in [run, run_vm] {
+        assert_eq!(run("(mod(-7, 3), -7 % 3)").value(), "(2, -1)");
+        assert_eq!(run("(mod(7, -3), 7 % -3)").value(), "(1, 1)");
+        assert_eq!(run("(mod(-7, -3), -7 % -3)").value(), "(2, -1)");
+    }
+}
+
+#[test]
+fn mod_by_zero_is_an_error() {
+    for run in [run, run_vm] {
+        assert_eq!(run("mod(1, 0)").error(), "invalid argument passed to mod: division by zero");
+    }
+}
+
+#[test]
+fn arrays_grow_by_push_and_read_back_by_get() {
+    for run in [run, run_vm] {
+        let source = r#"let a = array(); let _ = push(a, 1); let _ = push(a, "two"); (len(a), get(a, 0), get(a, 1), a)"#;
+        assert_eq!(run(source).value(), "(2, 1, two, [1, two])");
+    }
+}
+
+#[test]
+fn arrays_are_shared_by_reference() {
+    for run in [run, run_vm] {
+        assert!(matches!(run("let a = array(); let b = a; let _ = push(b, 1); len(a)").value, Ok(RuntimeValue::Int(1))));
+    }
+}
+
+#[test]
+fn get_out_of_bounds_is_an_error() {
+    for run in [run, run_vm] {
+        let run = |source| run(source).error();
+        assert_eq!(run("get(array(), 0)"), "invalid argument passed to get: index 0 is out of bounds for an array of 0 elements");
+        assert_eq!(run("let a = array(); let _ = push(a, 1); get(a, -1)"), "invalid argument passed to get: index -1 is out of bounds for an array of 1 elements");
+        assert_eq!(run("push(1, 2)"), "type mismatch: push expects an array and a value");
+    }
+}
+
+#[test]
+fn str_len_counts_characters_rather_than_bytes() {
+    for run in [run, run_vm] {
+        assert_eq!(run(r#"(str_len("hello"), str_len(""), str_len("café"), str_len("🎉a"))"#).value(), "(5, 0, 4, 2)");
+    }
+}
+
+#[test]
+fn substr_slices_by_character() {
+    for run in [run, run_vm] {
+        assert_eq!(run(r#"substr("hello", 1, 3)"#).value.unwrap(), RuntimeValue::Str("el".into()));
+        assert_eq!(run(r#"(substr("café!", 2, 4), substr("🎉ab", 0, 1), substr("abc", 1, 1))"#).value(), "(fé, 🎉, )");
+    }
+}
+
+#[test]
+fn substr_out_of_range_is_an_error() {
+    for run in [run, run_vm] {
+        let run = |source| run(source).error();
+        assert_eq!(run(r#"substr("abc", 2, 4)"#), "invalid argument passed to substr: range 2..4 is out of bounds for a string of 3 characters");
+        assert_eq!(run(r#"substr("🎉", 0, 2)"#), "invalid argument passed to substr: range 0..2 is out of bounds for a string of 1 characters");
+        assert_eq!(run(r#"substr("abc", 2, 1)"#), "invalid argument passed to substr: range 2..1 is out of bounds for a string of 3 characters");
+    }
+}
+
+#[test]
+fn concat_joins_any_number_of_strings_and_numbers() {
+    for run in [run, run_vm] {
+        assert_eq!(run(r#"concat("a", "b")"#).value.unwrap(), RuntimeValue::Str("ab".into()));
+        assert_eq!(run(r#"concat("a", 1, "c")"#).value.unwrap(), RuntimeValue::Str("a1c".into()));
+        assert_eq!(run("concat()").value.unwrap(), RuntimeValue::Str("".into()));
+    }
+}
+
+#[test]
+fn concat_fails_on_other_values() {
+    for run in [run, run_vm] {
+        assert_eq!(run(r#"concat("x", (1, 2))"#).error(), "type mismatch: concat expects strings or numbers");
+    }
+}
+
+#[test]
+fn passing_assertions_are_void() {
+    for run in [run, run_vm] {
+        for source in ["assert(true)", "assert(1)", r#"assert(1 + 1 == 2, "ok")"#] {
+            assert_eq!(run(source).value.unwrap(), RuntimeValue::Void(()), "{source}");
+        }
+    }
+}
+
+#[test]
+fn failing_assertions_are_errors_where_they_were_called() {
+    let source = "let x = 1;\nlet _ = assert(x == 2);\nprint(x)";
+    for run in [run, run_vm] {
+        let run = run(source);
+        let error = run.value.expect_err("the assertion should fail");
+        assert_eq!(error.to_string(), "assertion failed");
+        assert_eq!(error.location().unwrap().line_column(source), (2, 9));
+        assert_eq!(run.output, "");
+    }
+}
+
+#[test]
+fn failing_assertions_carry_the_message_given() {
+    for run in [run, run_vm] {
+        assert_eq!(run(r#"assert(0, "bad math")"#).error(), "bad math");
+    }
+}
+
+/// What `source` evaluates to on both executors when `read_line` reads
+/// from `input`, and the interpreter allows I/O if `io` is set.
+fn reading(source: &str, input: &'static [u8], io: bool) -> EvalResult {
+    let file = parser::parse_or_report("test.rinha", source).unwrap();
+    let eval = |vm| {
+        let mut interpreter = interpreter(Config { io, ..Config::default() }, &Output::default());
+        interpreter.input = Some(Box::new(std::io::Cursor::new(input)));
+        match vm {
+            true => interpreter.eval_vm(&file.expression),
+            false => interpreter.eval(&file.expression),
+        }
+    };
+    let interpreted = eval(false);
+    assert_eq!(eval(true).ok(), interpreted.as_ref().ok().cloned(), "{source} on the vm");
+    interpreted
+}
+
+#[test]
+fn read_line_reads_a_line_without_its_break() {
+    assert_eq!(reading("read_line()", b"hello\r\nworld\n", true).unwrap(), RuntimeValue::Str("hello".into()));
+    assert_eq!(reading("read_line()", b"", true).unwrap(), RuntimeValue::Void(()));
+    assert_eq!(reading("(read_line(), read_line())", b"\nlast", true).unwrap().to_string(), "(, last)");
+}
+
+#[test]
+fn read_line_is_only_there_when_io_is_allowed() {
+    assert_eq!(reading("read_line()", b"hello\n", false).unwrap_err().to_string(), "undefined variable \"read_line\"");
+}
+
+#[test]
+fn a_program_reads_two_lines_from_the_injected_input() {
+    let source = "let a = to_int(read_line()); let b = to_int(read_line()); a + b";
+    assert_eq!(reading(source, b"40\n2\n", true).unwrap(), RuntimeValue::Int(42));
+}
+
+#[test]
+fn pow_raises_integers_and_floats() {
+    for run in [run, run_vm] {
+        assert_eq!(run("pow(2, 10)").value.unwrap(), RuntimeValue::Int(1024));
+        assert_eq!(run("(pow(0, 0), pow(-2, 31), pow(2.0, -1))").value(), "(1, -2147483648, 0.5)");
+    }
+}
+
+#[test]
+fn pow_past_32_bits_overflows() {
+    for run in [run, run_vm] {
+        assert_eq!(run("pow(2, 63)").error(), "invalid argument passed to pow: 2 to the power of 63 overflows");
+        assert_eq!(run("pow(2, 31)").error(), "invalid argument passed to pow: 2 to the power of 31 overflows");
+    }
+}
+
+#[test]
+fn pow_of_an_integer_has_no_negative_exponents() {
+    for run in [run, run_vm] {
+        assert_eq!(run("pow(2, -1)").error(), "invalid argument passed to pow: negative exponent -1 for the integer 2");
+    }
+}
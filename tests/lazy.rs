@@ -0,0 +1,56 @@
+//! Passing arguments by need, with `--lazy`.
+
+mod common;
+
+use caramuru::Config;
+use common::{run_with, Run};
+
+fn run_lazy(source: &str) -> Run {
+    run_with(source, Config { lazy: true, ..Config::default() }, false)
+}
+
+#[test]
+fn unused_argument_is_never_evaluated() {
+    let run = run_lazy(r#"let f = fn (a, b) => { a }; f(1, print("evaluated"))"#);
+    assert_eq!(run.value(), "1");
+    assert_eq!(run.output, "");
+}
+
+#[test]
+fn argument_is_evaluated_at_most_once() {
+    let run = run_lazy(r#"let twice = fn (x) => { x + x }; twice(print(21))"#);
+    assert_eq!(run.value(), "42");
+    assert_eq!(run.output, "21");
+}
+
+#[test]
+fn eager_calls_still_evaluate_every_argument() {
+    let run = run_with(r#"let f = fn (a, b) => { a }; f(1, print("evaluated"))"#, Config::default(), false);
+    assert_eq!(run.output, "evaluated");
+}
+
+#[test]
+fn closure_bound_in_a_lazy_argument_keeps_its_parameters() {
+    // The closure is created while its thunk is forced, in a frame of its
+    // own, which mustn't move its parameters from where they were resolved.
+    let run = run_lazy("let id = fn (v) => { v }; print(id({ let g = fn (n) => { n + 1 }; g(1) }))");
+    assert_eq!(run.output, "2");
+}
+
+#[test]
+fn recursive_closure_bound_in_a_function_under_lazy() {
+    let source = "
+        let f = fn (n) => {
+            let go = fn (i, acc) => { if (i == 0) { acc } else { go(i - 1, acc + i) } };
+            go(n, 0)
+        };
+        f(10)
+    ";
+    assert_eq!(run_lazy(source).value(), "55");
+}
+
+#[test]
+fn arguments_read_the_globals_their_caller_saw() {
+    let source = "let x = 1; let id = fn (v) => { v }; let f = fn () => { id(x) }; let x = 2; (f(), x)";
+    assert_eq!(run_lazy(source).value(), "(1, 2)");
+}
@@ -0,0 +1,69 @@
+//! Turning the byte offsets of locations into lines and columns, and where
+//! errors say they happened.
+
+mod common;
+
+use caramuru::ast::Location;
+use common::run;
+
+fn line_column(start: usize, source: &str) -> (usize, usize) {
+    Location::new(start, start, "test.rinha").line_column(source)
+}
+
+#[test]
+fn offsets_map_to_lines_and_columns() {
+    let source = "let x = 1;\nlet y = x;\n\nprint(y)";
+    assert_eq!(line_column(0, source), (1, 1));
+    assert_eq!(line_column(4, source), (1, 5));
+    assert_eq!(line_column(10, source), (1, 11));
+    assert_eq!(line_column(11, source), (2, 1));
+    assert_eq!(line_column(19, source), (2, 9));
+    assert_eq!(line_column(22, source), (3, 1));
+    assert_eq!(line_column(23, source), (4, 1));
+}
+
+#[test]
+fn columns_count_characters_rather_than_bytes() {
+    let source = "let s = \"çã\"; zz";
+    assert_eq!(line_column(source.find("zz").unwrap(), source), (1, 15));
+}
+
+#[test]
+fn offsets_past_the_end_stop_at_the_end() {
+    assert_eq!(line_column(100, "a\nbc"), (2, 3));
+}
+
+#[test]
+fn parsed_locations_display_as_lines_and_columns() {
+    let error = run("let x = 1;\n  x + zz").value.expect_err("the program should fail");
+    assert_eq!(error.location().unwrap().to_string(), "test.rinha:2:7");
+}
+
+#[test]
+fn unparsed_locations_display_as_byte_offsets() {
+    assert_eq!(Location::new(28, 30, "unparsed.json").to_string(), "unparsed.json, byte 28");
+}
+
+#[test]
+fn pairs_errors_know_where_they_are() {
+    let source = "let x = 1;\nlet y = first(x);\ny";
+    let error = run(source).value.expect_err("the program should fail");
+    assert_eq!(error.to_string(), "type mismatch: first applied to a value that is not a pair");
+    assert_eq!(error.location().unwrap().line_column(source), (2, 9));
+}
+
+#[test]
+fn undefined_variables_know_where_they_are() {
+    let source = "let x = 1;\n  x + zz";
+    let error = run(source).value.expect_err("the program should fail");
+    assert_eq!(error.to_string(), "undefined variable \"zz\"");
+    assert_eq!(error.location().unwrap().line_column(source), (2, 7));
+}
+
+#[test]
+fn snippets_underline_the_location_on_its_line() {
+    let source = "let x = 1;\nprint(x + zz)\nx";
+    assert_eq!(Location::new(21, 23, "test.rinha").snippet(source), "print(x + zz)\n          ^^");
+    assert_eq!(Location::new(0, 0, "test.rinha").snippet(source), "let x = 1;\n^");
+    assert_eq!(Location::new(1, 3, "test.rinha").snippet("\tab"), "\tab\n\t^^");
+}
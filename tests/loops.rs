@@ -0,0 +1,80 @@
+//! Reporting calls that repeat themselves forever, with `--detect-loops`.
+
+mod common;
+
+use caramuru::{Config, InterpreterError};
+use common::{run_with, with_large_stack, Run};
+
+fn detect_loops() -> Config {
+    Config { detect_loops: true, ..Config::default() }
+}
+
+fn run_detecting(source: &str) -> Run {
+    run_with(source, detect_loops(), false)
+}
+
+#[test]
+fn call_repeating_itself_in_tail_position_is_reported() {
+    let run = run_detecting("let f = fn (n) => { f(n) }; f(1)");
+    assert!(matches!(run.value, Err(InterpreterError::InfiniteRecursion { .. })));
+    assert_eq!(run.error(), "infinite recursion: f(1) kept calling itself with the same arguments");
+}
+
+#[test]
+fn call_repeating_itself_at_a_growing_depth_is_reported() {
+    let run = run_detecting("let f = fn (n, m) => { if (m > 0) { 1 + f(n, m) } else { 0 } }; f(\"x\", 1)");
+    assert!(matches!(run.value, Err(InterpreterError::InfiniteRecursion { .. })));
+}
+
+#[test]
+fn tail_calls_cycling_through_arguments_are_reported() {
+    let run = run_detecting("let g = fn (n) => { if (n == 3) { g(1) } else { g(n + 1) } }; g(1)");
+    assert!(matches!(run.value, Err(InterpreterError::InfiniteRecursion { .. })));
+}
+
+#[test]
+fn deep_recursion_with_changing_arguments_is_not_reported() {
+    with_large_stack(|| {
+        let run = run_detecting("let f = fn (n) => { if (n == 0) { 0 } else { 1 + f(n - 1) } }; f(5000)");
+        assert_eq!(run.value(), "5000");
+    })
+}
+
+#[test]
+fn recursion_growing_an_array_is_not_reported() {
+    let source = "
+        let xs = array();
+        let grow = fn (n) => { if (len(xs) == 3) { len(xs) } else { let _ = push(xs, 1); grow(n) } };
+        grow(0)
+    ";
+    assert_eq!(run_detecting(source).value(), "3");
+}
+
+#[test]
+fn recursion_growing_an_array_through_another_function_is_not_reported() {
+    let source = "
+        let xs = array();
+        let add = fn (x) => { push(xs, x) };
+        let grow = fn (n) => { if (len(xs) == 3) { len(xs) } else { let _ = add(n); grow(n) } };
+        grow(0)
+    ";
+    assert_eq!(run_detecting(source).value(), "3");
+}
+
+#[test]
+fn recursion_through_another_function_is_not_reported() {
+    with_large_stack(|| {
+        let config = Config { max_depth: 100, ..detect_loops() };
+        let source = "let f = fn (n) => { 1 + g(n) }; let g = fn (n) => { 1 + f(n) }; f(1)";
+        assert!(matches!(run_with(source, config, false).value, Err(InterpreterError::StackOverflow { .. })));
+    })
+}
+
+#[test]
+fn loops_are_not_reported_without_the_flag() {
+    with_large_stack(|| {
+        let config = Config { max_depth: 100, ..Config::default() };
+        let run = run_with("let f = fn (n) => { 1 + f(n) }; f(1)", config, false);
+        assert!(matches!(run.value, Err(InterpreterError::StackOverflow { .. })));
+    })
+}
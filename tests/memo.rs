@@ -0,0 +1,35 @@
+//! Caching the results of pure recursive functions, with `--memoize`.
+
+mod common;
+
+use caramuru::{parser, Config};
+use common::{interpreter, run_with, Output};
+
+const FIB: &str = "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(25)";
+
+/// The calls a program makes, and what it evaluates to.
+fn calls(source: &str, memoize: bool) -> (usize, String) {
+    let output = Output::default();
+    let mut interpreter = interpreter(Config { memoize, stats: true, ..Config::default() }, &output);
+    let file = parser::parse_or_report("test.rinha", source).unwrap();
+    let value = interpreter.eval(&file.expression).unwrap();
+    (interpreter.stats.unwrap().calls, value.to_string())
+}
+
+#[test]
+fn repeated_calls_hit_the_cache() {
+    let (uncached, expected) = calls(FIB, false);
+    let (cached, value) = calls(FIB, true);
+    assert_eq!(value, expected);
+    assert_eq!(value, "75025");
+    // Every n from 25 down to 0 is computed once.
+    assert_eq!(cached, 26);
+    assert!(uncached > 100_000);
+}
+
+#[test]
+fn functions_reading_globals_give_the_same_results_as_without_the_cache() {
+    let source = "let k = 1; let g = fn (x) => { x + k }; let a = g(1); let k = 10; (a, g(1))";
+    let expected = run_with(source, Config::default(), false).value();
+    assert_eq!(run_with(source, Config { memoize: true, ..Config::default() }, false).value(), expected);
+}
@@ -0,0 +1,174 @@
+//! Binary and unary operators.
+
+mod common;
+
+use caramuru::RuntimeValue;
+use common::{run, run_vm};
+
+#[test]
+fn plus_concatenates_strings_with_strings_and_numbers() {
+    assert!(matches!(run(r#""con" + "cat""#).value, Ok(RuntimeValue::Str(x)) if x == "concat"));
+    assert_eq!(run(r#"("n = " + 1, (2 + "nd", 1 + 2))"#).value(), "(n = 1, (2nd, 3))");
+}
+
+#[test]
+fn plus_fails_to_concatenate_a_string_with_a_bool() {
+    assert!(run(r#""x" + true"#).error().contains("operand is not a string or a number"));
+}
+
+#[test]
+fn arithmetic_operators_give_integers() {
+    assert!(matches!(run("2 * 3").value, Ok(RuntimeValue::Int(6))));
+    assert_eq!(run("(1 + 2, (7 - 3, (6 / 4, 7 % 4)))").value(), "(3, (4, (1, 3)))");
+}
+
+#[test]
+fn comparisons_give_bools() {
+    assert!(matches!(run("1 < 2").value, Ok(RuntimeValue::Bool(true))));
+    assert_eq!(run("(1 == 1, (1 != 1, (2 > 3, (2 <= 2, 3 >= 4))))").value(), "(true, (false, (false, (true, false))))");
+}
+
+#[test]
+fn logical_operators_take_the_bools_comparisons_give() {
+    assert_eq!(run("let t = 1 < 2; let f = 2 < 1; (t && t, (t || f, (f || f, 1 && 0)))").value(), "(true, (true, (false, false)))");
+}
+
+#[test]
+fn strings_compare_for_equality() {
+    assert_eq!(run(r#"("a" == "a", ("a" == "b", ("a" != "b", "a" != "a")))"#).value(), "(true, (false, (true, false)))");
+}
+
+#[test]
+fn strings_never_equal_integers() {
+    assert_eq!(run(r#"("1" == 1, 1 != "1")"#).value(), "(false, true)");
+}
+
+#[test]
+fn bools_compare_for_equality() {
+    assert_eq!(run("(true == true, (true == false, (true != false, false != false)))").value(), "(true, (false, (true, false)))");
+}
+
+#[test]
+fn values_of_different_types_are_unequal() {
+    assert_eq!(run(r#"(true == 1, (1 != false, "true" == true))"#).value(), "(false, (true, false))");
+}
+
+#[test]
+fn logical_operators_take_bools_and_integers() {
+    assert_eq!(run("(true && false, (false || true, (true && 1, 0 || false)))").value(), "(false, (true, (true, false)))");
+}
+
+#[test]
+fn logical_operators_fail_on_strings() {
+    assert!(run(r#"true && "yes""#).error().contains("logical operand is not a boolean or an integer"));
+}
+
+#[test]
+fn arithmetic_fails_on_bools() {
+    assert!(run("true + 1").error().contains("operator + expects integer operands"));
+}
+
+#[test]
+fn logical_operators_skip_the_right_side_once_the_left_decides() {
+    let source = "let boom = fn () => { first(0) }; (false && boom(), true || boom())";
+    assert_eq!(run(source).value(), "(false, true)");
+}
+
+#[test]
+fn logical_operators_evaluate_the_right_side_when_it_decides() {
+    assert!(run("let boom = fn () => { first(0) }; true && boom()").error().contains("first applied to a value that is not a pair"));
+}
+
+#[test]
+fn dividing_by_zero_is_an_error() {
+    assert_eq!(run("10 / 0").error(), "division by zero: 10 / 0");
+    assert_eq!(run("10 % 0").error(), "division by zero: 10 % 0");
+}
+
+#[test]
+fn overflowing_is_an_error() {
+    assert_eq!(run("2147483647 * 2").error(), "arithmetic overflow: 2147483647 * 2");
+    assert_eq!(run("2147483647 + 1").error(), "arithmetic overflow: 2147483647 + 1");
+    assert_eq!(run("(0 - 2147483647) - 2").error(), "arithmetic overflow: -2147483647 - 2");
+}
+
+#[test]
+fn minus_negates_literals_and_terms() {
+    assert!(matches!(run("-5").value, Ok(RuntimeValue::Int(-5))));
+    assert_eq!(run("(- -5, (--5, (-(3 - 5), 2 * -3)))").value(), "(5, (5, (2, -6)))");
+    assert_eq!(run("let x = 4; -x + 1").value(), "-3");
+}
+
+#[test]
+fn negating_the_smallest_int_is_an_overflow_error() {
+    assert_eq!(run("let x = (-2147483647) - 1; -x").error(), "arithmetic overflow: 0 - -2147483648");
+}
+
+#[test]
+fn floats_promote_ints_in_arithmetic() {
+    assert!(matches!(run("1.5 + 2").value, Ok(RuntimeValue::Float(x)) if x == 3.5));
+    assert!(matches!(run("3.0 / 2.0").value, Ok(RuntimeValue::Float(x)) if x == 1.5));
+    assert_eq!(run("(7 / 2.0, (2.0 * 3, (3.0, -1.5)))").value(), "(3.5, (6, (3, -1.5)))");
+}
+
+#[test]
+fn floats_compare_with_floats_and_ints() {
+    assert_eq!(run("(1.5 < 2, (2.0 == 2, (0.1 + 0.2 > 0.3, (2.5 >= 2.5, 2.5 != 2.5))))").value(), "(true, (true, (true, (true, false))))");
+}
+
+#[test]
+fn strings_compare_lexicographically() {
+    assert!(matches!(run(r#""apple" < "banana""#).value, Ok(RuntimeValue::Bool(true))));
+    assert!(matches!(run(r#""b" > "a""#).value, Ok(RuntimeValue::Bool(true))));
+    assert_eq!(run(r#"("a" <= "a", ("abc" < "abd", ("B" >= "a", "ab" > "abc")))"#).value(), "(true, (true, (false, false)))");
+}
+
+#[test]
+fn comparing_a_string_with_a_number_is_a_type_mismatch() {
+    for run in [run, run_vm] {
+        assert_eq!(run(r#""a" < 1"#).error(), "type mismatch: operator < expects two numbers or two strings");
+        assert_eq!(run(r#"1 >= "a""#).error(), "type mismatch: operator >= expects two numbers or two strings");
+    }
+}
+
+#[test]
+fn concatenation_and_print_keep_multi_byte_characters() {
+    for run in [run, run_vm] {
+        let run = run(r#"let s = "café" + " 🎉"; print(s + "!")"#);
+        assert_eq!(run.output, "café 🎉!");
+        assert_eq!(run.value.unwrap(), RuntimeValue::Str("café 🎉!".into()));
+    }
+}
+
+#[test]
+fn string_literals_unescape_their_escapes() {
+    for run in [run, run_vm] {
+        assert_eq!(run(r#""a\tb\n\"q\" \\""#).value.unwrap(), RuntimeValue::Str("a\tb\n\"q\" \\".into()));
+    }
+}
+
+#[test]
+fn bitwise_operators_work_on_ints() {
+    for run in [run, run_vm] {
+        assert_eq!(run("6 & 3").value.unwrap(), RuntimeValue::Int(2));
+        assert_eq!(run("6 | 3").value.unwrap(), RuntimeValue::Int(7));
+        assert_eq!(run("6 ^ 3").value.unwrap(), RuntimeValue::Int(5));
+        assert_eq!(run("1 << 4").value.unwrap(), RuntimeValue::Int(16));
+        assert_eq!(run("(-16 >> 2, 1 << 31, 1 >> 31)").value(), "(-4, -2147483648, 0)");
+    }
+}
+
+#[test]
+fn shifting_by_a_negative_or_too_large_amount_is_an_error() {
+    for run in [run, run_vm] {
+        assert_eq!(run("1 << -1").error(), "invalid shift: 1 << -1, the amount must be from 0 to 31");
+        assert_eq!(run("1 >> 32").error(), "invalid shift: 1 >> 32, the amount must be from 0 to 31");
+    }
+}
+
+#[test]
+fn bitwise_operators_fail_on_other_types() {
+    for run in [run, run_vm] {
+        assert_eq!(run("true & 1").value.expect_err("the operands aren't ints").kind(), "TypeMismatch");
+    }
+}
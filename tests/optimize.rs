@@ -0,0 +1,135 @@
+//! The `-O` rewrite of linear recursion into tail calls, checked against
+//! the same programs run without it.
+
+mod common;
+
+use caramuru::{optimize, parser, printer, Config};
+use common::{interpreter, with_large_stack, Output};
+
+/// What a program evaluates to and prints, after `-O` when `optimized` is
+/// set, or the message it fails with.
+fn run(source: &str, optimized: bool, vm: bool) -> String {
+    let source = source.to_string();
+    with_large_stack(move || run_here(&source, optimized, vm))
+}
+
+fn run_here(source: &str, optimized: bool, vm: bool) -> String {
+    let mut file = parser::parse_or_report("test.rinha", source).expect("the program should parse");
+    if optimized {
+        optimize::optimize(&mut file.expression);
+    }
+    let output = Output::default();
+    let mut interpreter = interpreter(Config::default(), &output);
+    let value = match vm {
+        true => interpreter.eval_vm(&file.expression),
+        false => interpreter.eval(&file.expression),
+    };
+    match value {
+        Ok(x) => format!("{}{x}", output.text()),
+        Err(e) => format!("{}error: {e}", output.text()),
+    }
+}
+
+fn is_rewritten(source: &str) -> bool {
+    let mut file = parser::parse_or_report("test.rinha", source).unwrap();
+    optimize::optimize(&mut file.expression);
+    printer::render(&file.expression).contains("'step")
+}
+
+/// Checks that `-O` doesn't change what a program gives on either executor.
+fn assert_same(source: &str) -> String {
+    let expected = run(source, false, false);
+    for vm in [false, true] {
+        assert_eq!(run(source, true, vm), expected, "optimized, on the vm: {vm}");
+        assert_eq!(run(source, false, vm), expected, "unoptimized, on the vm: {vm}");
+    }
+    expected
+}
+
+const SUM: &str = "let sum = fn (n) => { if (n == 0) { 0 } else { n + sum(n - 1) } }; sum(100)";
+
+#[test]
+fn sum_is_rewritten_and_gives_the_same_result() {
+    assert!(is_rewritten(SUM));
+    assert_eq!(assert_same(SUM), "5050");
+}
+
+#[test]
+fn product_is_rewritten_and_gives_the_same_result() {
+    let source = "let fact = fn (n) => { if (n < 2) { 1 } else { fact(n - 1) * n } }; fact(10)";
+    assert!(is_rewritten(source));
+    assert_eq!(assert_same(source), "3628800");
+}
+
+#[test]
+fn fold_over_two_parameters_is_rewritten_and_gives_the_same_result() {
+    let source = "
+        let sum_squares = fn (from, to) => { if (from > to) { 0 } else { from * from + sum_squares(from + 1, to) } };
+        sum_squares(1, 10)
+    ";
+    assert!(is_rewritten(source));
+    assert_eq!(assert_same(source), "385");
+}
+
+#[test]
+fn overflow_is_still_reported() {
+    // The operands are multiplied in another order, so the overflow is hit
+    // on other ones.
+    let source = "let fact = fn (n) => { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(30)";
+    assert!(is_rewritten(source));
+    for vm in [false, true] {
+        assert!(run(source, true, vm).starts_with("error: arithmetic overflow"));
+    }
+}
+
+#[test]
+fn fold_over_strings_is_left_alone() {
+    let source = r#"
+        let f = fn (s, t, k) => { if (k == 0) { s } else { f(s, t, k - 1) + t } };
+        f("s", "t", 2)
+    "#;
+    assert!(!is_rewritten(source));
+    assert_eq!(assert_same(source), "stt");
+}
+
+#[test]
+fn fold_over_a_parameter_called_with_a_variable_is_left_alone() {
+    let source = r#"
+        let t = "t";
+        let f = fn (x, y, k) => { if (k == 0) { x } else { f(x, y, k - 1) + y } };
+        f(1, t, 2)
+    "#;
+    assert!(!is_rewritten(source));
+    assert_eq!(assert_same(source), "1tt");
+}
+
+#[test]
+fn fold_over_a_captured_variable_is_left_alone() {
+    let source = r#"
+        let s = "s";
+        let t = "t";
+        let f = fn (k) => { if (k == 0) { s } else { f(k - 1) + t } };
+        f(2)
+    "#;
+    assert!(!is_rewritten(source));
+    assert_eq!(assert_same(source), "stt");
+}
+
+#[test]
+fn function_used_as_a_value_is_left_alone() {
+    let source = r#"
+        let f = fn (s, k) => { if (k == 0) { s } else { s + f(s, k - 1) } };
+        let apply = fn (g) => { g("a", 2) };
+        apply(f)
+    "#;
+    assert!(!is_rewritten(source));
+    assert_eq!(assert_same(source), "aaa");
+}
+
+#[test]
+fn deep_sum_runs_only_once_rewritten() {
+    let source = SUM.replace("sum(100)", "sum(50000)");
+    assert_eq!(run(&source, true, false), "1250025000");
+    assert_eq!(run(&source, true, true), "1250025000");
+    assert!(run(&source, false, false).starts_with("error: stack overflow"));
+}
@@ -0,0 +1,55 @@
+//! Parsing source: the literals it accepts and the errors it reports.
+
+mod common;
+
+use caramuru::{parser, InterpreterError, RuntimeValue};
+use common::{run, run_vm};
+
+/// The first error parsing `source` fails with: its message and the span
+/// of the source it points at.
+fn parse_error(source: &str) -> (String, std::ops::Range<usize>) {
+    let error = parser::parse_or_report("test.rinha", source).unwrap_err();
+    let first = &error.errors()[0];
+    let span = first.span();
+    (first.to_string(), span.offset()..span.offset() + span.len())
+}
+
+#[test]
+fn hex_and_binary_literals_are_integers() {
+    for run in [run, run_vm] {
+        assert_eq!(run("0x1F").value.unwrap(), RuntimeValue::Int(31));
+        assert_eq!(run("0b1010").value.unwrap(), RuntimeValue::Int(10));
+        assert_eq!(run("(0xff + 1, 0xAb, 0b0, 0x7fffffff)").value(), "(256, 171, 0, 2147483647)");
+    }
+}
+
+#[test]
+fn malformed_hex_and_binary_literals_point_at_the_offending_part() {
+    assert_eq!(parse_error("0b102"), ("unrecognized token `2`".to_string(), 4..5));
+    assert_eq!(parse_error("1 + 0x"), ("unrecognized token `x`".to_string(), 5..6));
+    assert_eq!(parse_error("0x80000000"), ("integer literal out of range".to_string(), 0..10));
+}
+
+/// The message, span and expected tokens of the error evaluating `source`
+/// fails to parse with.
+fn expected(source: &str) -> (String, (usize, usize), Vec<String>) {
+    match caramuru::eval_str(source).unwrap_err() {
+        InterpreterError::ParseError { message, location, expected } => (message, (location.start, location.end), expected),
+        x => panic!("{source} didn't fail to parse: {x}"),
+    }
+}
+
+#[test]
+fn an_unexpected_token_lists_what_could_come_instead() {
+    let (message, span, expected) = expected("let x = ;");
+    assert_eq!((message.as_str(), span), ("unrecognized token `;`", (8, 9)));
+    for token in [r#""(""#, r#""fn""#, r#""if""#, r#""true""#] {
+        assert!(expected.iter().any(|x| x == token), "{token} in {expected:?}");
+    }
+    assert!(!expected.iter().any(|x| x == r#"")""#), "{expected:?}");
+}
+
+#[test]
+fn an_unexpected_end_of_file_points_at_the_end() {
+    assert_eq!(expected("print(1"), ("expected token, but got eof".to_string(), (7, 7), vec![r#"")""#.to_string()]));
+}
@@ -0,0 +1,63 @@
+//! Functions bound by a `let` calling themselves from their own body.
+
+mod common;
+
+use caramuru::Config;
+use common::{run, run_vm, run_with, with_large_stack};
+
+#[test]
+fn tail_calls_run_in_constant_native_stack() {
+    // On the small stack tests run on, this only finishes if the calls
+    // don't nest.
+    let source = "let countdown = fn (n) => { if (n == 0) { \"done\" } else { countdown(n - 1) } }; countdown(1000000)";
+    assert_eq!(run(source).value(), "done");
+}
+
+#[test]
+fn endless_recursion_fails_cleanly_at_the_depth_limit() {
+    let error = with_large_stack(|| {
+        let source = "let forever = fn (n) => { 1 + forever(n + 1) }; forever(0)";
+        run_with(source, Config { max_depth: 500, ..Config::default() }, false).error()
+    });
+    assert_eq!(error, "stack overflow: call depth exceeded 500 calling forever");
+}
+
+#[test]
+fn self_recursive_factorial() {
+    let source = "let factorial = fn (n) => { if (n == 0) { 1 } else { n * factorial(n - 1) } }; factorial(10)";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "3628800");
+    }
+}
+
+#[test]
+fn recursive_function_bound_inside_another() {
+    let source = "
+        let factorial = fn (n) => {
+            let go = fn (i, acc) => { if (i == 0) { acc } else { go(i - 1, acc * i) } };
+            go(n, 1)
+        };
+        factorial(10)
+    ";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "3628800");
+    }
+}
+
+#[test]
+fn recursive_function_bound_in_a_block() {
+    let source = "let x = { let down = fn (n) => { if (n == 0) { 0 } else { down(n - 1) } }; down(5) }; x";
+    for run in [run, run_vm] {
+        assert_eq!(run(source).value(), "0");
+    }
+}
+
+#[test]
+fn recursion_deeper_than_the_native_stack_of_a_test() {
+    with_large_stack(|| {
+        let source = "let count = fn (n) => { if (n == 0) { 0 } else { 1 + count(n - 1) } }; count(9000)";
+        for run in [run, run_vm] {
+            assert_eq!(run(source).value(), "9000");
+        }
+    })
+}
@@ -0,0 +1,47 @@
+//! Running every program in a directory, by pointing the binary at it.
+
+use std::process::Command;
+
+/// A directory of its own under the system's temporary one, holding
+/// `files`.
+fn directory(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("caramuru-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+    for (file, source) in files {
+        std::fs::write(path.join(file), source).unwrap();
+    }
+    path
+}
+
+#[test]
+fn runs_every_program_and_fails_if_any_did() {
+    let path = directory("mixed", &[
+        ("a.rinha", "print(1)"),
+        ("b.rinha", "let _ = print(2); zz"),
+        ("c.rinha", "print(3)"),
+        ("notes.txt", "not a program"),
+    ]);
+    let output = Command::new(env!("CARGO_BIN_EXE_caramuru")).arg(&path).output().unwrap();
+    let file = |x: &str| path.join(x).to_string_lossy().into_owned();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout, format!(
+        "==> {} <==\n1\n==> {} <==\n2\n==> {} <==\n3\n",
+        file("a.rinha"), file("b.rinha"), file("c.rinha"),
+    ));
+    assert!(stderr.contains("error: undefined variable \"zz\""));
+    assert!(stderr.contains(&format!("    ok {}\nFAILED {}\n    ok {}\n", file("a.rinha"), file("b.rinha"), file("c.rinha"))));
+    assert!(stderr.ends_with("3 programs, 2 passed, 1 failed\n"));
+    std::fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn passes_when_every_program_does() {
+    let path = directory("passing", &[("a.rinha", "print(1)"), ("b.rinha", "print(2)")]);
+    let output = Command::new(env!("CARGO_BIN_EXE_caramuru")).arg(&path).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8(output.stderr).unwrap().ends_with("2 programs, 2 passed, 0 failed\n"));
+    std::fs::remove_dir_all(path).unwrap();
+}
@@ -0,0 +1,60 @@
+//! Which binding a name refers to: the callee's own rather than its
+//! caller's, and the one before when the top-level code binds it again.
+
+mod common;
+
+use caramuru::InterpreterError;
+use common::run;
+
+#[test]
+fn callee_sees_its_own_variable_rather_than_the_callers() {
+    let source = "
+        let callee = fn () => { let x = \"callee\"; x };
+        let caller = fn () => { let x = \"caller\"; (callee(), x) };
+        caller()
+    ";
+    assert_eq!(run(source).value(), "(callee, caller)");
+}
+
+#[test]
+fn callee_never_sees_the_parameters_of_its_caller() {
+    let run = run("let callee = fn () => { x }; let caller = fn (x) => { callee() }; caller(1)");
+    assert!(matches!(run.value, Err(InterpreterError::UndefinedVariable { name, .. }) if name == "x"));
+}
+
+#[test]
+fn closure_keeps_the_global_it_was_defined_after() {
+    assert_eq!(run("let x = 1; let f = fn () => { x }; let x = 2; f()").value(), "1");
+}
+
+#[test]
+fn rebinding_reads_the_binding_before_it() {
+    assert_eq!(run("let x = 1; let x = x + 1; let x = x * 10; x").value(), "20");
+}
+
+#[test]
+fn recursive_function_bound_again_calls_itself() {
+    let source = "
+        let f = fn (n) => { 0 };
+        let f = fn (n) => { if (n == 0) { 1 } else { 2 * f(n - 1) } };
+        f(3)
+    ";
+    assert_eq!(run(source).value(), "8");
+}
+
+#[test]
+fn functions_can_call_ones_bound_after_them() {
+    let source = "
+        let even = fn (n) => { if (n == 0) { true } else { odd(n - 1) } };
+        let odd = fn (n) => { if (n == 0) { false } else { even(n - 1) } };
+        even(10)
+    ";
+    assert_eq!(run(source).value(), "true");
+}
+
+#[test]
+fn thousands_of_chained_lets_run_without_overflowing() {
+    let bindings: String = (1..=2000).map(|i| format!("let x{i} = x{} + 1;\n", i - 1)).collect();
+    let source = format!("let x0 = 0;\n{bindings}let x0 = x2000 * 2;\n(x0, x1)");
+    assert_eq!(run(&source).value(), "(4000, 1)");
+}
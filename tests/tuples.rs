@@ -0,0 +1,67 @@
+//! Tuples: building them, reading their elements and printing them.
+
+mod common;
+
+use caramuru::RuntimeValue;
+use common::{run, run_json};
+
+#[test]
+fn pair_from_a_json_ast() {
+    let pair = run_json(r#"{"kind": "Tuple", "first": {"kind": "Int", "value": 10, {loc}}, "second": {"kind": "Int", "value": 20, {loc}}, {loc}}"#);
+    assert!(matches!(&pair.value, Ok(RuntimeValue::Tuple(x)) if matches!(x[..], [RuntimeValue::Int(10), RuntimeValue::Int(20)])));
+}
+
+#[test]
+fn first_reads_the_first_element_of_a_pair() {
+    assert!(matches!(run("first((1, 2))").value, Ok(RuntimeValue::Int(1))));
+}
+
+#[test]
+fn first_fails_on_anything_but_a_pair() {
+    assert!(run("first(5)").error().contains("first applied to a value that is not a pair"));
+}
+
+#[test]
+fn second_reads_the_second_element_of_a_pair() {
+    assert!(matches!(run("second((1, 2))").value, Ok(RuntimeValue::Int(2))));
+}
+
+#[test]
+fn second_fails_on_anything_but_a_pair() {
+    assert!(run("second(\"pair\")").error().contains("second applied to a value that is not a pair"));
+}
+
+#[test]
+fn tuple_bound_by_a_let_reads_back_every_time() {
+    assert!(matches!(run("let t = (1, (2, 3)); first(t) + second(second(t))").value, Ok(RuntimeValue::Int(4))));
+}
+
+#[test]
+fn tuples_display_their_elements() {
+    assert_eq!(run(r#"(1, ("two", (true, 4)))"#).value(), "(1, (two, (true, 4)))");
+}
+
+#[test]
+fn tuples_compare_element_by_element() {
+    assert_eq!(run("(1, 2) == (1, 2)").value(), "true");
+    assert_eq!(run("(1, 2) == (1, 3)").value(), "false");
+    assert_eq!(run(r#"((1, 2) != (1, 3), (1, (2, "a")) == (1, (2, "a")))"#).value(), "(true, true)");
+}
+
+#[test]
+fn tuples_of_different_lengths_are_unequal() {
+    assert_eq!(run("((1, 2) == (1, 2, 3), (1, 2) != (1, 2, 3))").value(), "(false, true)");
+}
+
+#[test]
+fn triple_builds_and_prints_every_element() {
+    let run = run(r#"let t = (1, "b", true); let _ = print(t); t"#);
+    assert_eq!(run.output, "(1, b, true)");
+    assert!(matches!(&run.value, Ok(RuntimeValue::Tuple(x)) if x.len() == 3));
+}
+
+#[test]
+fn first_and_second_only_take_pairs_while_get_takes_any_tuple() {
+    assert!(run("second((1, 2, 3))").error().starts_with("type mismatch: second applied to a value that is not a pair"));
+    assert_eq!(run("(get((1, 2, 3), 0), get((1, 2, 3), 2))").value(), "(1, 3)");
+}
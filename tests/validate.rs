@@ -0,0 +1,69 @@
+//! The problems validation finds in malformed syntax trees before they run.
+
+mod common;
+
+use caramuru::{parser, validate::validate};
+use common::file_json;
+
+/// The messages of the problems in a JSON AST expression, as in
+/// [`file_json`].
+fn problems(expression: &str) -> Vec<String> {
+    validate(&file_json(expression).expression).into_iter().map(|x| x.message).collect()
+}
+
+/// The problems in a program parsed from source, with where they start.
+fn source_problems(source: &str) -> Vec<(String, usize)> {
+    let file = parser::parse_or_report("test.rinha", source).unwrap();
+    validate(&file.expression).into_iter().map(|x| (x.message, x.location.start)).collect()
+}
+
+#[test]
+fn a_well_formed_program_has_no_problems() {
+    assert!(source_problems("let f = fn (a, b) => { (a, b) }; print(first(f(1, 2)))").is_empty());
+}
+
+#[test]
+fn error_terms_are_parse_errors() {
+    let error = r#"{"kind": "Error", "message": "unexpected token", "full_text": "?", {loc}}"#;
+    assert_eq!(problems(error), ["parse error: unexpected token"]);
+}
+
+#[test]
+fn names_cannot_be_empty() {
+    let var = r#"{"kind": "Var", "text": "", {loc}}"#;
+    assert_eq!(problems(var), ["variable with an empty name"]);
+    let function = r#"{"kind": "Function", "parameters": [{"text": "", {loc}}], "value": {"kind": "Int", "value": 1, {loc}}, {loc}}"#;
+    assert_eq!(problems(function), ["parameter with an empty name"]);
+    let binding = r#"{"kind": "Let", "name": {"text": "", {loc}}, "value": {"kind": "Int", "value": 1, {loc}}, "next": {"kind": "Int", "value": 2, {loc}}, {loc}}"#;
+    assert_eq!(problems(binding), ["binding with an empty name"]);
+}
+
+#[test]
+fn literals_cannot_be_called() {
+    let call = r#"{"kind": "Call", "callee": {"kind": "Int", "value": 1, {loc}}, "arguments": [], {loc}}"#;
+    assert_eq!(problems(call), ["callee is not a function"]);
+}
+
+#[test]
+fn parameters_cannot_be_declared_twice() {
+    assert_eq!(source_problems("fn (a, b, a) => { a }"), [("parameter \"a\" is declared twice".to_string(), 10)]);
+}
+
+#[test]
+fn first_and_second_need_something_that_can_be_a_pair() {
+    assert_eq!(
+        source_problems("(first(5), second((1, 2, 3)), first(x))"),
+        [
+            ("first applied to a value that is not a pair".to_string(), 1),
+            ("second applied to a value that is not a pair".to_string(), 11),
+        ]
+    );
+}
+
+#[test]
+fn problems_are_listed_in_the_order_they_appear() {
+    let problems = source_problems("let f = fn (a, a) => { first(1) }; second(true)");
+    let starts: Vec<usize> = problems.iter().map(|x| x.1).collect();
+    assert_eq!(problems.len(), 3);
+    assert!(starts.windows(2).all(|x| x[0] < x[1]), "{problems:?}");
+}
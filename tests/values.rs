@@ -0,0 +1,135 @@
+//! How values print and hash.
+
+mod common;
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use caramuru::{HashKey, InterpreterError, RuntimeValue};
+use common::{run, run_vm};
+
+fn string(x: &str) -> RuntimeValue {
+    RuntimeValue::Str(x.to_string())
+}
+
+fn array(items: Vec<RuntimeValue>) -> RuntimeValue {
+    RuntimeValue::Array(Rc::new(RefCell::new(items)))
+}
+
+#[test]
+fn each_variant_displays_as_print_writes_it() {
+    assert_eq!(RuntimeValue::Int(-42).to_string(), "-42");
+    assert_eq!(string("olá").to_string(), "olá");
+    assert_eq!(RuntimeValue::Bool(false).to_string(), "false");
+    assert_eq!(RuntimeValue::Tuple(vec![RuntimeValue::Int(1), string("a")]).to_string(), "(1, a)");
+    assert_eq!(RuntimeValue::Void(()).to_string(), "");
+    assert_eq!(run("fn (x) => { x }").value(), "<#closure>");
+}
+
+fn key(value: &RuntimeValue) -> HashKey {
+    HashKey::try_from(value).unwrap()
+}
+
+#[test]
+fn equal_values_hash_to_the_same_key() {
+    let pair = || RuntimeValue::Tuple(vec![RuntimeValue::Int(1), string("a")]);
+    let mut map = HashMap::new();
+    map.insert(key(&pair()), "pair");
+    map.insert(key(&RuntimeValue::Bool(true)), "true");
+    assert_eq!(map.get(&key(&pair())), Some(&"pair"));
+    assert_eq!(map.get(&key(&RuntimeValue::Bool(true))), Some(&"true"));
+    assert_eq!(map.len(), 2);
+}
+
+#[test]
+fn distinct_values_hash_to_distinct_keys() {
+    assert_ne!(key(&RuntimeValue::Int(1)), key(&string("1")));
+    assert_ne!(key(&RuntimeValue::Int(1)), key(&RuntimeValue::Bool(true)));
+    let pair = |x| RuntimeValue::Tuple(vec![RuntimeValue::Int(1), RuntimeValue::Int(x)]);
+    assert_ne!(key(&pair(2)), key(&pair(3)));
+}
+
+#[test]
+fn functions_and_void_are_not_hashable() {
+    let function = run("fn (x) => { x }").value.unwrap();
+    for value in [function, RuntimeValue::Void(()), RuntimeValue::Tuple(vec![RuntimeValue::Void(())])] {
+        assert!(matches!(HashKey::try_from(&value), Err(InterpreterError::Unhashable { .. })));
+    }
+}
+
+#[test]
+fn clones_print_like_what_they_were_cloned_from() {
+    let function = run("fn (x) => { x }").value.unwrap();
+    let values = [
+        RuntimeValue::Int(1),
+        RuntimeValue::Float(2.5),
+        string("s"),
+        RuntimeValue::Bool(true),
+        RuntimeValue::Tuple(vec![RuntimeValue::Int(1), RuntimeValue::Tuple(vec![string("a"), RuntimeValue::Bool(false)])]),
+        array(vec![RuntimeValue::Int(1)]),
+        function,
+        RuntimeValue::Void(()),
+    ];
+    for value in values {
+        assert_eq!(value.clone().to_string(), value.to_string());
+    }
+}
+
+#[test]
+fn cloned_arrays_are_the_same_array() {
+    let original = array(vec![]);
+    let RuntimeValue::Array(clone) = original.clone() else { unreachable!() };
+    clone.borrow_mut().push(RuntimeValue::Int(1));
+    assert_eq!(original.to_string(), "[1]");
+}
+
+#[test]
+fn debug_shows_the_variant_and_hides_closures() {
+    let pair = RuntimeValue::Tuple(vec![RuntimeValue::Int(1), string("a")]);
+    assert_eq!(format!("{pair:?}"), r#"Tuple([Int(1), Str("a")])"#);
+    assert_eq!(format!("{:?}", array(vec![RuntimeValue::Bool(true)])), "Array([Bool(true)])");
+    assert_eq!(format!("{:?}", RuntimeValue::Float(0.5)), "Float(0.5)");
+    assert_eq!(format!("{:?}", RuntimeValue::Void(())), "Void");
+    assert_eq!(format!("{:?}", run("fn (x) => { x }").value.unwrap()), "Function { .. }");
+}
+
+#[test]
+fn values_equal_others_of_the_same_type_and_contents() {
+    assert_eq!(RuntimeValue::Int(1), RuntimeValue::Float(1.0));
+    assert_eq!(string("a"), string("a"));
+    assert_eq!(array(vec![RuntimeValue::Int(1)]), array(vec![RuntimeValue::Int(1)]));
+    let nested = || RuntimeValue::Tuple(vec![RuntimeValue::Int(1), RuntimeValue::Tuple(vec![RuntimeValue::Bool(true)])]);
+    assert_eq!(nested(), nested());
+}
+
+#[test]
+fn values_of_other_types_or_contents_are_unequal() {
+    assert_ne!(RuntimeValue::Int(1), RuntimeValue::Int(2));
+    assert_ne!(RuntimeValue::Int(1), string("1"));
+    assert_ne!(RuntimeValue::Int(1), RuntimeValue::Bool(true));
+    assert_ne!(RuntimeValue::Int(0), RuntimeValue::Void(()));
+    assert_ne!(RuntimeValue::Tuple(vec![RuntimeValue::Int(1)]), array(vec![RuntimeValue::Int(1)]));
+    assert_ne!(RuntimeValue::Tuple(vec![RuntimeValue::Int(1)]), RuntimeValue::Tuple(vec![RuntimeValue::Int(1), RuntimeValue::Int(2)]));
+}
+
+#[test]
+fn functions_are_only_equal_to_the_same_closure() {
+    let function = run("fn (x) => { x }").value.unwrap();
+    assert_eq!(function.clone(), function);
+    assert_ne!(run("fn (x) => { x }").value.unwrap(), function);
+}
+
+#[test]
+fn void_is_only_equal_to_itself() {
+    for run in [run, run_vm] {
+        let source = r#"let v = assert(true); (v == v, v == 0, v == "", v == false, v != 1)"#;
+        assert_eq!(run(source).value(), "(true, false, false, false, true)");
+    }
+}
+
+#[test]
+fn void_prints_as_nothing_and_joins_strings_as_the_empty_one() {
+    for run in [run, run_vm] {
+        let run = run(r#"let v = assert(true); let _ = print(v); print(("[" + v + "]", v))"#);
+        assert_eq!(run.output, "([], )");
+    }
+}
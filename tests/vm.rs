@@ -0,0 +1,81 @@
+//! The bytecode machine against the tree-walking interpreter: every
+//! program here has to print, evaluate to and fail with the same on both.
+
+mod common;
+
+use common::{run, run_vm, with_large_stack};
+
+const PROGRAMS: &[(&str, &str)] = &[
+    ("arithmetic", "(1 + 2 * 3, 10 / 3, 10 % 3, 7 - 2, -7 % 3)"),
+    ("comparisons", r#"(1 < 2, 2 <= 2, 3 > 4, 4 >= 5, "a" < "b", 1 == 1, "a" != "a")"#),
+    ("logic", "(true && false, true || false, 1 && 0, false || 2)"),
+    ("floats", "(1.5 + 1, 3 / 2.0, 0.1 + 0.2 == 0.3)"),
+    ("strings", r#"let name = "mundo"; print("olá, " + name + "! " + 1 + 2)"#),
+    ("tuples", "let t = (1, (2, 3)); (first(t), second(second(t)), get((4, 5, 6), 2), len((1, 2, 3)))"),
+    ("if", "let f = fn (x) => { if (x > 0) { \"positive\" } else { if (x == 0) { \"zero\" } else { \"negative\" } } }; (f(1), f(0), f(-1))"),
+    ("blocks", "let x = 1; let y = { let x = 2; let _ = print(x); x * 10 }; x + y"),
+    ("print", "let _ = print(print((1, \"a\"))); print(fn (x) => { x })"),
+    ("fib", "let fib = fn (n) => { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(20)"),
+    ("tail calls", "let sum = fn (n, acc) => { if (n == 0) { acc } else { sum(n - 1, acc + n) } }; sum(100000, 0)"),
+    ("deep recursion", "let sum = fn (n) => { if (n == 0) { 0 } else { n + sum(n - 1) } }; sum(5000)"),
+    ("closures", "let add = fn (a) => { fn (b) => { a + b } }; let inc = add(1); (inc(1), inc(10))"),
+    ("nested closures", "
+        let f = fn (a) => { let g = fn (b) => { let h = fn (c) => { a + b + c }; h(3) }; g(2) };
+        f(1)
+    "),
+    ("local recursion", "
+        let f = fn (n) => { let go = fn (i, acc) => { if (i > n) { acc } else { go(i + 1, acc * i) } }; go(1, 1) };
+        f(10)
+    "),
+    ("mutual recursion", "
+        let even = fn (n) => { if (n == 0) { true } else { odd(n - 1) } };
+        let odd = fn (n) => { if (n == 0) { false } else { even(n - 1) } };
+        (even(10), odd(7))
+    "),
+    ("higher order", "
+        let map = fn (f, t) => { (f(first(t)), f(second(t))) };
+        map(fn (x) => { x * x }, (3, 4))
+    "),
+    ("shadowing", "let x = 1; let f = fn (x) => { let x = x + 1; x }; let x = f(x); (x, f(10))"),
+    ("rebound globals", "let x = 1; let f = fn () => { x }; let x = 2; (f(), x)"),
+    ("intrinsics", r#"(to_int(" 42 "), to_str((1, 2)))"#),
+    ("undefined variable", "let f = fn () => { zz }; f()"),
+    ("arity", "let f = fn (a, b) => { a }; f(1)"),
+    ("not a function", "let f = 1; f(2)"),
+    ("type mismatch", r#"(1, true - "a")"#),
+    ("division by zero", "let f = fn (x) => { 10 / x }; f(0)"),
+    ("overflow", "2147483647 + 1"),
+    ("not a pair", "first((1, 2, 3))"),
+    ("bad condition", "if (\"yes\") { 2 } else { 3 }"),
+];
+
+fn assert_same(name: &str, source: &str) {
+    let interpreted = run(source);
+    let compiled = run_vm(source);
+    assert_eq!(compiled.output, interpreted.output, "{name}: output");
+    match (&interpreted.value, &compiled.value) {
+        (Ok(x), Ok(y)) => assert_eq!(y.to_string(), x.to_string(), "{name}: value"),
+        (Err(x), Err(y)) => assert_eq!(y.to_string(), x.to_string(), "{name}: error"),
+        (Ok(x), Err(y)) => panic!("{name}: the interpreter gave {x}, the vm failed with {y}"),
+        (Err(x), Ok(y)) => panic!("{name}: the interpreter failed with {x}, the vm gave {y}"),
+    }
+}
+
+#[test]
+fn programs_give_the_same_on_both() {
+    with_large_stack(|| {
+        for (name, source) in PROGRAMS {
+            assert_same(name, source);
+        }
+    })
+}
+
+#[test]
+fn examples_give_the_same_on_both() {
+    with_large_stack(|| {
+        for entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/examples")).unwrap() {
+            let path = entry.unwrap().path();
+            assert_same(&path.to_string_lossy(), &std::fs::read_to_string(&path).unwrap());
+        }
+    })
+}